@@ -3,7 +3,7 @@ while ! /bin/kafka-topics --bootstrap-server localhost:9092 --list; do
     sleep 1
 done
 
-for topic in mqa-events; do
+for topic in mqa-events mqa-scores; do
     kafka-topics --bootstrap-server localhost:9092 \
         --create --if-not-exists \
         --partitions 4 \
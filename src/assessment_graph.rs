@@ -1,13 +1,18 @@
-use std::{collections::HashMap, io::Cursor};
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufRead, Cursor},
+};
 
 use chrono::{DateTime, NaiveDateTime, Utc};
 use oxigraph::{
-    io::{RdfFormat, RdfParser},
+    io::{RdfFormat, RdfParser, RdfSerializer},
     model::{
         vocab::xsd, BlankNode, GraphNameRef, Literal, NamedNode, NamedNodeRef, NamedOrBlankNode,
-        Quad, Term,
+        NamedOrBlankNodeRef, Quad, Subject, Term,
     },
-    store::Store,
+    sparql::QuerySolution,
+    store::{LoaderError, Store},
 };
 use sophia_api::{
     graph::Graph,
@@ -20,18 +25,32 @@ use sophia_turtle::parser::turtle;
 
 use crate::{
     error::Error,
-    helpers::{execute_query, named_quad_object, named_quad_subject},
+    helpers::{
+        execute_query, named_or_blank_quad_object, named_quad_object, named_quad_subject,
+        sparql_iri,
+    },
     measurement_value::MeasurementValue,
     score::{DimensionScore, MetricScore, Score},
-    vocab::{dcat_mqa, dcat_terms, dqv, rdf_syntax},
+    vocab::{dcat_mqa, dcat_terms, dqv, rdf_syntax, NAMESPACE_ALIASES, PREFIXES},
 };
 
 #[derive(Debug, PartialEq)]
 pub struct AssessmentNode {
     pub assessment: NamedNode,
-    pub resource: NamedNode,
+    pub resource: NamedOrBlankNode,
+}
+
+/// Raw quality measurement node, with the assessment containing it.
+#[derive(Debug, PartialEq)]
+pub struct MeasurementTriple {
+    pub assessment: NamedNode,
+    pub measurement: NamedOrBlankNode,
+    pub metric: NamedNode,
+    pub computed_on: Option<NamedOrBlankNode>,
 }
 
+/// Clones share the same store, e.g. to load it from a blocking task.
+#[derive(Clone)]
 pub struct AssessmentGraph(oxigraph::store::Store);
 
 impl AssessmentGraph {
@@ -43,12 +62,74 @@ impl AssessmentGraph {
 
     /// Loads graph from string.
     pub fn load<G: ToString>(&self, graph: G) -> Result<(), Error> {
-        self.0.load_from_reader(
+        self.load_reader(graph.to_string().as_bytes())
+    }
+
+    /// Loads graph from reader, parsing it as it is read.
+    /// The https variants of the dcat and dqv namespaces are normalized to http.
+    pub fn load_reader<R: BufRead>(&self, reader: R) -> Result<(), Error> {
+        self.load_with(
             RdfParser::from_format(RdfFormat::Turtle)
                 .without_named_graphs()
                 .with_default_graph(GraphNameRef::DefaultGraph),
-            graph.to_string().as_bytes().as_ref()
-        )?;
+            reader,
+        )
+    }
+
+    /// Loads graph from string, relabelling its blank nodes to keep them distinct from those
+    /// already loaded, e.g. when merging separately serialized assessments.
+    pub fn load_distinct<G: ToString>(&self, graph: G) -> Result<(), Error> {
+        self.load_with(
+            RdfParser::from_format(RdfFormat::Turtle)
+                .without_named_graphs()
+                .with_default_graph(GraphNameRef::DefaultGraph)
+                .rename_blank_nodes(),
+            graph.to_string().as_bytes(),
+        )
+    }
+
+    fn load_with<R: BufRead>(&self, parser: RdfParser, reader: R) -> Result<(), Error> {
+        for quad in parser.for_reader(reader) {
+            let quad = quad.map_err(LoaderError::from)?;
+            self.0.insert(&normalize_namespaces(quad))?;
+        }
+        Ok(())
+    }
+
+    /// Loads graph from string, failing on any triple containing an invalid IRI.
+    pub fn load_validated<G: ToString>(&self, graph: G) -> Result<(), Error> {
+        let quads = RdfParser::from_format(RdfFormat::Turtle)
+            .without_named_graphs()
+            .with_default_graph(GraphNameRef::DefaultGraph)
+            .unchecked()
+            .for_reader(graph.to_string().as_bytes())
+            .collect::<Result<Vec<Quad>, _>>()
+            .map_err(|e| format!("unable to parse graph: {e}"))?;
+
+        for quad in quads.iter() {
+            let subject = match &quad.subject {
+                Subject::NamedNode(node) => Some(node),
+                _ => None,
+            };
+            let object = match &quad.object {
+                Term::NamedNode(node) => Some(node),
+                _ => None,
+            };
+            for node in [subject, Some(&quad.predicate), object]
+                .into_iter()
+                .flatten()
+            {
+                NamedNode::new(node.as_str()).map_err(|e| {
+                    Error::Permanent(format!(
+                        "invalid IRI <{}> in triple '{}': {e}",
+                        node.as_str(),
+                        quad
+                    ))
+                })?;
+            }
+        }
+
+        self.0.extend(quads.into_iter().map(normalize_namespaces))?;
         Ok(())
     }
 
@@ -72,7 +153,7 @@ impl AssessmentGraph {
         })
     }
 
-    pub fn assessment_resource(&self, assessment: NamedNodeRef) -> Result<NamedNode, Error> {
+    pub fn assessment_resource(&self, assessment: NamedNodeRef) -> Result<NamedOrBlankNode, Error> {
         self.0
             .quads_for_pattern(
                 Some(assessment.into()),
@@ -80,7 +161,7 @@ impl AssessmentGraph {
                 None,
                 None,
             )
-            .map(named_quad_object)
+            .map(named_or_blank_quad_object)
             .next()
             .unwrap_or(Err(format!(
                 "assessment graph has no resource that '{}' is assessment of",
@@ -89,9 +170,10 @@ impl AssessmentGraph {
             .into()))
     }
 
-    /// Retrieves all named distribution assessment nodes.
+    /// Retrieves all named distribution assessment nodes, either typed as distribution
+    /// assessment or linked from a dataset assessment.
     pub fn distributions(&self) -> Result<Vec<AssessmentNode>, Error> {
-        let distributions = self
+        let mut assessments = self
             .0
             .quads_for_pattern(
                 None,
@@ -100,7 +182,22 @@ impl AssessmentGraph {
                 None,
             )
             .map(named_quad_subject)
-            .collect::<Result<Vec<NamedNode>, Error>>()?
+            .collect::<Result<Vec<NamedNode>, Error>>()?;
+        for quad in self.0.quads_for_pattern(
+            None,
+            Some(dcat_mqa::HAS_DISTRIBUTION_ASSESSMENTS),
+            None,
+            None,
+        ) {
+            let assessment = named_quad_object(quad)?;
+            if !assessments.contains(&assessment) {
+                assessments.push(assessment);
+            }
+        }
+        // Sorted for scores to be posted in the same order, whatever the store order.
+        assessments.sort();
+
+        let distributions = assessments
             .into_iter()
             .map(|assessment| {
                 let resource = self.assessment_resource(assessment.as_ref())?;
@@ -110,13 +207,58 @@ impl AssessmentGraph {
                 })
             })
             .collect::<Result<Vec<AssessmentNode>, Error>>()?;
+        validate_distributions(&distributions)?;
         Ok(distributions)
     }
 
-    /// Retrieves all quality measurements in a graph, as map: (node, metric) -> value.
+    /// Retrieves all quality measurements in a graph, as map: (node, metric) -> values.
     pub fn quality_measurements(
         &self,
-    ) -> Result<HashMap<(NamedNode, NamedNode), MeasurementValue>, Error> {
+    ) -> Result<HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>, Error> {
+        self.measurements_where("")
+    }
+
+    /// Retrieves quality measurements matching `restriction`, e.g. of a single node,
+    /// as map: (node, metric) -> values.
+    fn measurements_where(
+        &self,
+        restriction: &str,
+    ) -> Result<HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>, Error> {
+        let query = format!(
+            "
+            SELECT ?node ?metric ?value
+            WHERE {{
+                {}
+                ?node {} ?measurement .
+                ?measurement {} ?metric .
+                OPTIONAL {{ ?measurement {} ?value . }}
+            }}
+        ",
+            restriction,
+            dcat_mqa::CONTAINS_QUALITY_MEASUREMENT,
+            dqv::IS_MEASUREMENT_OF,
+            dqv::VALUE
+        );
+        let mut measurements: HashMap<_, Vec<MeasurementValue>> = HashMap::new();
+        for qs in execute_query(&self.0, &query)? {
+            // A single malformed measurement should not prevent scoring of the rest.
+            match quality_measurement(&qs) {
+                Ok((key, value)) => measurements.entry(key).or_default().push(value),
+                Err(e) => tracing::warn!(
+                    error = e.to_string(),
+                    node = qs.get("node").map(|node| node.to_string()),
+                    metric = qs.get("metric").map(|metric| metric.to_string()),
+                    "skipping malformed quality measurement"
+                ),
+            }
+        }
+        Ok(measurements)
+    }
+
+    /// Hash of all (node, metric, value) measurement tuples, independent of their order
+    /// and of measurement node labels. Only comparable within the same process.
+    /// Total scores are left out, so a scored graph hashes the same as its measurements.
+    pub fn measurements_hash(&self) -> Result<u64, Error> {
         let query = format!(
             "
             SELECT ?node ?metric ?value
@@ -130,27 +272,140 @@ impl AssessmentGraph {
             dqv::IS_MEASUREMENT_OF,
             dqv::VALUE
         );
+        let mut tuples = execute_query(&self.0, &query)?
+            .into_iter()
+            .filter_map(
+                |qs| match (qs.get("node"), qs.get("metric"), qs.get("value")) {
+                    (_, Some(Term::NamedNode(metric)), _)
+                        if dcat_mqa::is_scoring(metric.as_ref()) =>
+                    {
+                        None
+                    }
+                    (Some(node), Some(metric), Some(value)) => {
+                        Some(Ok(format!("{node} {metric} {value}")))
+                    }
+                    _ => Some(Err("unable to get quality measurement".into())),
+                },
+            )
+            .collect::<Result<Vec<String>, Error>>()?;
+        tuples.sort();
+        tuples.dedup();
+
+        let mut hasher = DefaultHasher::new();
+        tuples.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Retrieves quality measurements of a single node, as map: metric -> values.
+    pub fn measurements_for(
+        &self,
+        node: NamedNodeRef,
+    ) -> Result<HashMap<NamedNode, Vec<MeasurementValue>>, Error> {
+        Ok(self
+            .measurements_where(&format!("VALUES ?node {{ {} }}", sparql_iri(node)?))?
+            .into_iter()
+            .map(|((_, metric), values)| (metric, values))
+            .collect())
+    }
+
+    /// Retrieves all quality measurement nodes, with their metric and computed-on resource.
+    pub fn measurement_nodes(&self) -> Result<Vec<MeasurementTriple>, Error> {
+        let query = format!(
+            "
+            SELECT ?assessment ?measurement ?metric ?resource
+            WHERE {{
+                ?assessment {} ?measurement .
+                ?measurement {} ?metric .
+                OPTIONAL {{ ?measurement {} ?resource . }}
+            }}
+        ",
+            dcat_mqa::CONTAINS_QUALITY_MEASUREMENT,
+            dqv::IS_MEASUREMENT_OF,
+            dqv::COMPUTED_ON
+        );
         execute_query(&self.0, &query)?
             .into_iter()
             .map(|qs| {
-                let node = match qs.get("node") {
+                let assessment = match qs.get("assessment") {
                     Some(Term::NamedNode(node)) => Ok(node.clone()),
+                    _ => Err("unable to get quality measurement assessment"),
+                }?;
+                let measurement = match qs.get("measurement") {
+                    Some(Term::NamedNode(node)) => Ok(node.clone().into()),
+                    Some(Term::BlankNode(node)) => Ok(node.clone().into()),
                     _ => Err("unable to get quality measurement node"),
                 }?;
                 let metric = match qs.get("metric") {
                     Some(Term::NamedNode(node)) => Ok(node.clone()),
                     _ => Err("unable to get quality measurement metric"),
                 }?;
-                let value = match qs.get("value") {
-                    Some(Term::Literal(value)) => MeasurementValue::try_from(value.clone()),
-                    _ => Err("unable to get quality measurement value".into()),
-                }?;
-                Ok(((node, metric), value))
+                let computed_on = match qs.get("resource") {
+                    Some(Term::NamedNode(node)) => Some(node.clone().into()),
+                    Some(Term::BlankNode(node)) => Some(node.clone().into()),
+                    _ => None,
+                };
+                Ok(MeasurementTriple {
+                    assessment,
+                    measurement,
+                    metric,
+                    computed_on,
+                })
             })
             .collect()
     }
 
-    /// Inserts modification timestamp.
+    /// Measurements that are not scored, as they are contained in an assessment that is
+    /// neither the dataset nor a distribution assessment, e.g. due to a typo by the producer.
+    pub fn orphan_measurements(&self) -> Result<Vec<MeasurementTriple>, Error> {
+        let assessments = self.assessment_resources()?;
+        Ok(self
+            .measurement_nodes()?
+            .into_iter()
+            .filter(|triple| !assessments.contains_key(&triple.assessment))
+            .collect())
+    }
+
+    /// Measurements contained in the dataset or a distribution assessment, but computed on
+    /// a resource that is not the one of that assessment, e.g. due to a typo by the producer.
+    /// They are still scored as measurements of the assessment containing them.
+    pub fn misplaced_measurements(&self) -> Result<Vec<MeasurementTriple>, Error> {
+        let assessments = self.assessment_resources()?;
+        Ok(self
+            .measurement_nodes()?
+            .into_iter()
+            .filter(|triple| {
+                assessments
+                    .get(&triple.assessment)
+                    .zip(triple.computed_on.as_ref())
+                    .is_some_and(|(resource, computed_on)| computed_on != resource)
+            })
+            .collect())
+    }
+
+    /// Resources of the dataset and distribution assessments, as map: assessment -> resource.
+    fn assessment_resources(&self) -> Result<HashMap<NamedNode, NamedOrBlankNode>, Error> {
+        Ok(std::iter::once(self.dataset()?)
+            .chain(self.distributions()?)
+            .map(|node| (node.assessment, node.resource))
+            .collect())
+    }
+
+    /// Resources that quality measurements are computed on, as map: (node, metric) -> resource.
+    /// Measurements without a computed-on resource are left out.
+    pub fn measurement_resources(
+        &self,
+    ) -> Result<HashMap<(NamedNode, NamedNode), NamedOrBlankNode>, Error> {
+        Ok(self
+            .measurement_nodes()?
+            .into_iter()
+            .filter_map(|triple| {
+                let computed_on = triple.computed_on?;
+                Some(((triple.assessment, triple.metric), computed_on))
+            })
+            .collect())
+    }
+
+    /// Inserts modification timestamp, replacing any existing.
     pub fn insert_modified_timestmap(&self, timestamp: i64) -> Result<(), Error> {
         let timestamp = DateTime::<Utc>::from_naive_utc_and_offset(
             NaiveDateTime::from_timestamp_opt(
@@ -163,6 +418,18 @@ impl AssessmentGraph {
         .to_string();
 
         let dataset_assessment = self.dataset()?.assessment;
+        for quad in self
+            .0
+            .quads_for_pattern(
+                Some(dataset_assessment.as_ref().into()),
+                Some(dcat_terms::MODIFIED),
+                None,
+                None,
+            )
+            .collect::<Result<Vec<_>, _>>()?
+        {
+            self.0.remove(&quad)?;
+        }
         self.0.insert(&Quad::new(
             dataset_assessment.as_ref(),
             dcat_terms::MODIFIED,
@@ -172,6 +439,43 @@ impl AssessmentGraph {
         Ok(())
     }
 
+    /// Inserts name and version of this service, and time of scoring, replacing any existing.
+    pub fn insert_provenance(&self, generated_at: DateTime<Utc>) -> Result<(), Error> {
+        let dataset_assessment = self.dataset()?.assessment;
+        let provenance = [
+            (
+                dcat_terms::CREATOR,
+                Literal::new_simple_literal(format!(
+                    "{}/{}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                )),
+            ),
+            (
+                dcat_terms::CREATED,
+                Literal::new_typed_literal(generated_at.to_rfc3339(), xsd::DATE_TIME),
+            ),
+        ];
+
+        for (predicate, object) in provenance {
+            for quad in self.0.quads_for_pattern(
+                Some(dataset_assessment.as_ref().into()),
+                Some(predicate),
+                None,
+                Some(GraphNameRef::DefaultGraph),
+            ) {
+                self.0.remove(&quad?)?;
+            }
+            self.0.insert(&Quad::new(
+                dataset_assessment.as_ref(),
+                predicate,
+                object,
+                GraphNameRef::DefaultGraph,
+            ))?;
+        }
+        Ok(())
+    }
+
     /// Get modification timestamp.
     pub fn get_modified_timestmap(&self) -> Result<i64, Error> {
         let dataset_assessment = self.dataset()?.assessment;
@@ -200,15 +504,26 @@ impl AssessmentGraph {
         }
     }
 
-    /// Inserts score into measurement graph.
-    pub fn insert_scores(&self, scores: &Vec<Score>) -> Result<(), Error> {
+    /// Inserts scores into measurement graph.
+    pub fn insert_scores(&self, scores: &[Score]) -> Result<(), Error> {
         for Score {
             assessment,
             resource,
             dimensions,
             score: total_score,
+            ..
         } in scores
         {
+            // Guards against scores desynced by refactors, e.g. of aggregation or reduction.
+            debug_assert_eq!(
+                dimensions
+                    .iter()
+                    .map(|dimension| dimension.score)
+                    .sum::<u64>(),
+                *total_score,
+                "dimension scores of '{}' do not sum to its total score",
+                assessment
+            );
             self.insert_node_score(assessment.as_ref(), resource.as_ref(), total_score)?;
             for DimensionScore {
                 id: name,
@@ -216,6 +531,16 @@ impl AssessmentGraph {
                 score: total_score,
             } in dimensions
             {
+                debug_assert_eq!(
+                    metrics
+                        .iter()
+                        .map(|metric| metric.score.unwrap_or_default())
+                        .sum::<u64>(),
+                    *total_score,
+                    "metric scores of '{}' in '{}' do not sum to its total score",
+                    name,
+                    assessment
+                );
                 self.insert_dimension_score(
                     assessment.as_ref(),
                     resource.as_ref(),
@@ -234,11 +559,59 @@ impl AssessmentGraph {
         Ok(())
     }
 
+    /// Removes scores, modification timestamp and provenance, leaving only measurements,
+    /// e.g. before rescoring graphs merged from separately scored assessments.
+    pub fn remove_scores(&self) -> Result<(), Error> {
+        for quad in self
+            .0
+            .quads_for_pattern(None, Some(dqv::IS_MEASUREMENT_OF), None, None)
+            .collect::<Result<Vec<_>, _>>()?
+        {
+            let Term::NamedNode(metric) = &quad.object else {
+                continue;
+            };
+            if !dcat_mqa::is_scoring(metric.as_ref()) {
+                continue;
+            }
+            let measurement: Term = match &quad.subject {
+                Subject::NamedNode(node) => node.clone().into(),
+                Subject::BlankNode(node) => node.clone().into(),
+                _ => continue,
+            };
+            for quad in self
+                .0
+                .quads_for_pattern(Some(quad.subject.as_ref()), None, None, None)
+                .chain(
+                    self.0
+                        .quads_for_pattern(None, None, Some(measurement.as_ref()), None),
+                )
+                .collect::<Result<Vec<_>, _>>()?
+            {
+                self.0.remove(&quad)?;
+            }
+        }
+        for predicate in [
+            dcat_mqa::SCORE,
+            dcat_terms::MODIFIED,
+            dcat_terms::CREATOR,
+            dcat_terms::CREATED,
+        ] {
+            for quad in self
+                .0
+                .quads_for_pattern(None, Some(predicate), None, None)
+                .collect::<Result<Vec<_>, _>>()?
+            {
+                self.0.remove(&quad)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Insert total score of a node into graph.
     fn insert_node_score(
         &self,
         assessment: NamedNodeRef,
-        computed_on: NamedNodeRef,
+        computed_on: NamedOrBlankNodeRef,
         score: &u64,
     ) -> Result<(), Error> {
         self.insert_measurement_property(
@@ -254,25 +627,20 @@ impl AssessmentGraph {
     fn insert_dimension_score(
         &self,
         assessment: NamedNodeRef,
-        computed_on: NamedNodeRef,
+        computed_on: NamedOrBlankNodeRef,
         dimension: NamedNodeRef,
         score: &u64,
     ) -> Result<(), Error> {
-        let metric = NamedNode::new(format!("{}Scoring", dimension.as_str()).as_str())?;
-        self.insert_measurement_property(
-            assessment,
-            computed_on,
-            metric.as_ref(),
-            dqv::VALUE,
-            score,
-        )
+        let metric = dcat_mqa::dimension_scoring(dimension)
+            .ok_or_else(|| format!("no scoring metric defined for dimension '{}'", dimension))?;
+        self.insert_measurement_property(assessment, computed_on, metric, dqv::VALUE, score)
     }
 
     /// Insert measurement score into graph.
     fn insert_measurement_score(
         &self,
         assessment: NamedNodeRef,
-        computed_on: NamedNodeRef,
+        computed_on: NamedOrBlankNodeRef,
         metric: &MetricScore,
     ) -> Result<(), Error> {
         if let Some(score) = metric.score {
@@ -292,7 +660,7 @@ impl AssessmentGraph {
     fn insert_measurement_property(
         &self,
         assessment: NamedNodeRef,
-        computed_on: NamedNodeRef,
+        computed_on: NamedOrBlankNodeRef,
         metric: NamedNodeRef,
         property: NamedNodeRef,
         value: &u64,
@@ -333,12 +701,14 @@ impl AssessmentGraph {
             "
                 SELECT ?measurement
                 WHERE {{
-                    {node} {} ?measurement .
-                    ?measurement {} {metric} .
+                    {} {} ?measurement .
+                    ?measurement {} {} .
                 }}
             ",
+            sparql_iri(node)?,
             dcat_mqa::CONTAINS_QUALITY_MEASUREMENT,
             dqv::IS_MEASUREMENT_OF,
+            sparql_iri(metric)?,
         );
         let result = execute_query(&self.0, &q)?.into_iter().next();
         match result {
@@ -362,7 +732,7 @@ impl AssessmentGraph {
     fn insert_measurement(
         &self,
         assessment: NamedNodeRef,
-        computed_on: NamedNodeRef,
+        computed_on: NamedOrBlankNodeRef,
         metric: NamedNodeRef,
     ) -> Result<NamedOrBlankNode, Error> {
         let measurement = BlankNode::default();
@@ -401,6 +771,11 @@ impl AssessmentGraph {
         Ok(())
     }
 
+    /// Whether graph has no content.
+    pub fn is_empty(&self) -> Result<bool, Error> {
+        Ok(self.0.is_empty()?)
+    }
+
     /// Dump graph to string.
     pub fn to_turtle(&self) -> Result<String, Error> {
         let mut buff = Cursor::new(Vec::new());
@@ -410,6 +785,19 @@ impl AssessmentGraph {
         String::from_utf8(buff.into_inner()).map_err(|e| e.to_string().into())
     }
 
+    /// Dump graph to string, abbreviating IRIs with the standard prefixes.
+    pub fn to_prefixed_turtle(&self) -> Result<String, Error> {
+        let serializer = PREFIXES.iter().try_fold(
+            RdfSerializer::from_format(RdfFormat::Turtle),
+            |serializer, (name, iri)| serializer.with_prefix(*name, *iri),
+        )?;
+        let mut buff = Cursor::new(Vec::new());
+        self.0
+            .dump_graph_to_writer(GraphNameRef::DefaultGraph, serializer, &mut buff)?;
+
+        String::from_utf8(buff.into_inner()).map_err(|e| e.to_string().into())
+    }
+
     /// Dump graph to json.
     pub fn turtle_to_jsonld(&self, turtle: &str) -> Result<String, Error> {
         let graph: LightGraph = turtle::parse_str(turtle)
@@ -426,10 +814,87 @@ impl AssessmentGraph {
     }
 }
 
+/// Parses query solution of node, metric and value into a quality measurement.
+fn quality_measurement(
+    qs: &QuerySolution,
+) -> Result<((NamedNode, NamedNode), MeasurementValue), Error> {
+    let node = match qs.get("node") {
+        Some(Term::NamedNode(node)) => Ok(node.clone()),
+        _ => Err("unable to get quality measurement node"),
+    }?;
+    let metric = match qs.get("metric") {
+        Some(Term::NamedNode(node)) => Ok(node.clone()),
+        _ => Err("unable to get quality measurement metric"),
+    }?;
+    let value = match qs.get("value") {
+        Some(Term::Literal(value)) => MeasurementValue::try_from(value.clone()),
+        _ => Err("unable to get quality measurement value".into()),
+    }?;
+    Ok(((node, metric), value))
+}
+
+/// Rejects distribution assessments that are assessments of themselves, or
+/// that share their resource with another distribution assessment.
+fn validate_distributions(distributions: &Vec<AssessmentNode>) -> Result<(), Error> {
+    let mut assessments_by_resource: HashMap<&NamedOrBlankNode, &NamedNode> = HashMap::new();
+    for AssessmentNode {
+        assessment,
+        resource,
+    } in distributions
+    {
+        if matches!(resource, NamedOrBlankNode::NamedNode(node) if node == assessment) {
+            return Err(format!(
+                "distribution assessment '{}' is an assessment of itself",
+                assessment
+            )
+            .into());
+        }
+        if let Some(other) = assessments_by_resource.insert(resource, assessment) {
+            return Err(format!(
+                "distribution '{}' is assessed by both '{}' and '{}'",
+                resource, other, assessment
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Replaces IRIs in https variants of known namespaces with their http equivalents.
+fn normalize_namespaces(quad: Quad) -> Quad {
+    let normalize = |node: NamedNode| {
+        NAMESPACE_ALIASES
+            .iter()
+            .find_map(|(alias, namespace)| {
+                node.as_str()
+                    .strip_prefix(alias)
+                    .map(|local| NamedNode::new_unchecked(format!("{namespace}{local}")))
+            })
+            .unwrap_or(node)
+    };
+    Quad {
+        subject: match quad.subject {
+            Subject::NamedNode(node) => normalize(node).into(),
+            subject => subject,
+        },
+        predicate: normalize(quad.predicate),
+        object: match quad.object {
+            Term::NamedNode(node) => normalize(node).into(),
+            object => object,
+        },
+        graph_name: quad.graph_name,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test::{mqa_node, node, MEASUREMENT_GRAPH};
+    use crate::{
+        compare::canonical_triples,
+        score::{calculate_score, ScoreKind},
+        score_graph::default_score_definitions,
+        test::{mqa_node, node, MEASUREMENT_GRAPH},
+    };
 
     pub fn measurement_graph() -> AssessmentGraph {
         let graph = AssessmentGraph::new().unwrap();
@@ -437,6 +902,44 @@ mod tests {
         graph
     }
 
+    /// Reduces multi-valued quality measurements to the first value of each (node, metric).
+    fn first_measurement_values(
+        measurements: HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>,
+    ) -> HashMap<(NamedNode, NamedNode), MeasurementValue> {
+        measurements
+            .into_iter()
+            .filter_map(|(key, values)| values.into_iter().next().map(|value| (key, value)))
+            .collect()
+    }
+
+    #[test]
+    fn invalid_iri() {
+        let graph = AssessmentGraph::new().unwrap();
+        let err = graph
+            .load_validated(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#hasDistributionAssessment> <https://distribution assessment.foo> .
+                "#,
+            )
+            .unwrap_err();
+
+        assert!(err.is_permanent());
+        assert!(err
+            .to_string()
+            .starts_with("invalid IRI <https://distribution assessment.foo> in triple"));
+        assert!(graph.dataset().is_err());
+    }
+
+    #[test]
+    fn validated_load() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph.load_validated(MEASUREMENT_GRAPH).unwrap();
+        assert_eq!(
+            graph.dataset().unwrap(),
+            measurement_graph().dataset().unwrap()
+        );
+    }
+
     #[test]
     fn dataset() {
         let graph = measurement_graph();
@@ -445,7 +948,7 @@ mod tests {
             dataset,
             AssessmentNode {
                 assessment: node("https://dataset.assessment.foo"),
-                resource: node("https://dataset.foo"),
+                resource: node("https://dataset.foo").into(),
             }
         );
     }
@@ -457,22 +960,137 @@ mod tests {
         assert_eq!(
             distributions,
             vec![
+                AssessmentNode {
+                    assessment: node("https://distribution.assessment.a"),
+                    resource: node("https://distribution.a").into(),
+                },
                 AssessmentNode {
                     assessment: node("https://distribution.assessment.b"),
-                    resource: node("https://distribution.b"),
+                    resource: node("https://distribution.b").into(),
                 },
+            ]
+        );
+    }
+
+    #[test]
+    fn stable_distribution_order() {
+        let reversed = MEASUREMENT_GRAPH
+            .lines()
+            .rev()
+            .collect::<Vec<_>>()
+            .join("\n");
+        let orders = [MEASUREMENT_GRAPH, reversed.as_str(), MEASUREMENT_GRAPH].map(|turtle| {
+            let graph = AssessmentGraph::new().unwrap();
+            graph.load(turtle).unwrap();
+            graph.distributions().unwrap()
+        });
+        assert_eq!(orders[0], orders[1]);
+        assert_eq!(orders[0], orders[2]);
+    }
+
+    #[test]
+    fn measurements_hash() {
+        let hash = |turtle: &str| {
+            let graph = AssessmentGraph::new().unwrap();
+            graph.load(turtle).unwrap();
+            graph.measurements_hash().unwrap()
+        };
+        let relabelled = MEASUREMENT_GRAPH
+            .replace("_:a", "_:x")
+            .replace("_:b", "_:y");
+        assert_eq!(hash(MEASUREMENT_GRAPH), hash(&relabelled));
+
+        let changed = MEASUREMENT_GRAPH.replace("\"200\"", "\"404\"");
+        assert_ne!(hash(MEASUREMENT_GRAPH), hash(&changed));
+
+        // Scores inserted when the graph was last scored are not measurements.
+        let scored = AssessmentGraph::new().unwrap();
+        scored.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&scored, default_score_definitions()).unwrap();
+        scored
+            .insert_scores(&[vec![dataset_score], distribution_scores].concat())
+            .unwrap();
+        assert_eq!(hash(MEASUREMENT_GRAPH), hash(&scored.to_turtle().unwrap()));
+    }
+
+    #[test]
+    fn modified_timestamp_replaced() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph.load(MEASUREMENT_GRAPH).unwrap();
+        graph.insert_modified_timestmap(1647698566000).unwrap();
+        graph.insert_modified_timestmap(1647698567000).unwrap();
+        assert_eq!(graph.get_modified_timestmap().unwrap(), 1647698567000);
+    }
+
+    #[test]
+    fn linked_distributions() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#hasDistributionAssessment> <https://distribution.assessment.a> .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#hasDistributionAssessment> <https://distribution.assessment.b> .
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.a> .
+                <https://distribution.assessment.b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment> .
+                <https://distribution.assessment.b> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.b> .
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(
+            graph.distributions().unwrap(),
+            vec![
                 AssessmentNode {
                     assessment: node("https://distribution.assessment.a"),
-                    resource: node("https://distribution.a"),
+                    resource: node("https://distribution.a").into(),
+                },
+                AssessmentNode {
+                    assessment: node("https://distribution.assessment.b"),
+                    resource: node("https://distribution.b").into(),
                 },
             ]
         );
     }
 
+    #[test]
+    fn duplicate_distribution_resource() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://distribution.assessment.a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment> .
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.a> .
+                <https://distribution.assessment.b> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment> .
+                <https://distribution.assessment.b> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.a> .
+                "#,
+            )
+            .unwrap();
+
+        let err = graph.distributions().unwrap_err().to_string();
+        assert!(err.contains("'<https://distribution.a>' is assessed by both"));
+    }
+
+    #[test]
+    fn self_referential_distribution() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://distribution.assessment.a> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment> .
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.assessment.a> .
+                "#,
+            )
+            .unwrap();
+
+        let err = graph.distributions().unwrap_err().to_string();
+        assert!(err.contains("is an assessment of itself"));
+    }
+
     #[test]
     fn get_measurements() {
         let graph = measurement_graph();
-        let measurements = graph.quality_measurements().unwrap();
+        let measurements = first_measurement_values(graph.quality_measurements().unwrap());
 
         assert_eq!(measurements.len(), 4);
         assert_eq!(
@@ -505,6 +1123,268 @@ mod tests {
         );
     }
 
+    #[test]
+    fn node_measurements() {
+        let graph = measurement_graph();
+        let measurements = graph
+            .measurements_for(node("https://distribution.assessment.a").as_ref())
+            .unwrap();
+
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(
+            measurements.get(&mqa_node("accessUrlStatusCode")),
+            Some(&vec![MeasurementValue::Int(200)])
+        );
+        assert_eq!(
+            measurements.get(&mqa_node("formatAvailability")),
+            Some(&vec![MeasurementValue::Bool(false)])
+        );
+    }
+
+    #[test]
+    fn orphans() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(MEASUREMENT_GRAPH.replace(
+                "_:a <http://www.w3.org/ns/dqv#value>",
+                "_:a <http://www.w3.org/ns/dqv#computedOn> <https://dataset.foo> .\n_:a <http://www.w3.org/ns/dqv#value>",
+            ))
+            .unwrap();
+        assert_eq!(graph.orphan_measurements().unwrap(), vec![]);
+        assert_eq!(graph.misplaced_measurements().unwrap(), vec![]);
+
+        graph
+            .load(
+                r#"
+                <https://distribution.assessment.typo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:x .
+                _:x <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability> .
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:y .
+                _:y <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability> .
+                _:y <http://www.w3.org/ns/dqv#computedOn> <https://distribution.typo> .
+                "#,
+            )
+            .unwrap();
+        let orphans = graph.orphan_measurements().unwrap();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(
+            orphans[0].assessment,
+            node("https://distribution.assessment.typo")
+        );
+        assert_eq!(orphans[0].computed_on, None);
+
+        let misplaced = graph.misplaced_measurements().unwrap();
+        assert_eq!(misplaced.len(), 1);
+        assert_eq!(
+            misplaced[0].assessment,
+            node("https://distribution.assessment.a")
+        );
+        assert_eq!(
+            misplaced[0].computed_on,
+            Some(node("https://distribution.typo").into())
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "dimension scores of '<https://dataset.assessment.foo>' do not sum")]
+    fn inconsistent_scores() {
+        let graph = measurement_graph();
+        graph
+            .insert_scores(&[Score {
+                kind: ScoreKind::Dataset,
+                assessment: node("https://dataset.assessment.foo"),
+                resource: node("https://dataset.foo").into(),
+                dimensions: vec![DimensionScore {
+                    id: mqa_node("accessibility"),
+                    metrics: vec![MetricScore {
+                        id: mqa_node("downloadUrlAvailability"),
+                        score: Some(20),
+                        computed_on: None,
+                    }],
+                    score: 20,
+                }],
+                score: 30,
+                best_distribution: None,
+            }])
+            .unwrap();
+    }
+
+    #[test]
+    fn https_namespaces() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(MEASUREMENT_GRAPH.replace("http://www.w3.org/ns/", "https://www.w3.org/ns/"))
+            .unwrap();
+        let expected = measurement_graph();
+
+        assert_eq!(graph.dataset().unwrap(), expected.dataset().unwrap());
+        assert_eq!(
+            graph.distributions().unwrap(),
+            expected.distributions().unwrap()
+        );
+        assert_eq!(
+            graph.quality_measurements().unwrap(),
+            expected.quality_measurements().unwrap()
+        );
+        assert!(!graph
+            .to_turtle()
+            .unwrap()
+            .contains("https://www.w3.org/ns/"));
+    }
+
+    #[test]
+    fn measurement_node_triples() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(MEASUREMENT_GRAPH.replace(
+                "_:a <http://www.w3.org/ns/dqv#value>",
+                "_:a <http://www.w3.org/ns/dqv#computedOn> <https://dataset.foo> .\n_:a <http://www.w3.org/ns/dqv#value>",
+            ))
+            .unwrap();
+
+        let mut pairs = graph
+            .measurement_nodes()
+            .unwrap()
+            .into_iter()
+            .map(|triple| {
+                assert!(matches!(triple.measurement, NamedOrBlankNode::BlankNode(_)));
+                (
+                    triple.assessment.as_str().to_string(),
+                    triple.metric,
+                    triple.computed_on,
+                )
+            })
+            .collect::<Vec<_>>();
+        pairs.sort_by(|a, b| (&a.0, a.1.as_str()).cmp(&(&b.0, b.1.as_str())));
+
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    "https://dataset.assessment.foo".to_string(),
+                    mqa_node("downloadUrlAvailability"),
+                    Some(node("https://dataset.foo").into()),
+                ),
+                (
+                    "https://distribution.assessment.a".to_string(),
+                    mqa_node("accessUrlStatusCode"),
+                    None,
+                ),
+                (
+                    "https://distribution.assessment.a".to_string(),
+                    mqa_node("formatAvailability"),
+                    None,
+                ),
+                (
+                    "https://distribution.assessment.b".to_string(),
+                    mqa_node("formatAvailability"),
+                    None,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn multi_valued_measurements() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:b .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                _:b <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability> .
+                _:b <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#,
+            )
+            .unwrap();
+
+        let measurements = graph.quality_measurements().unwrap();
+        assert_eq!(measurements.len(), 1);
+
+        let mut values = measurements
+            .get(&(
+                node("https://dataset.assessment.foo"),
+                mqa_node("keywordAvailability"),
+            ))
+            .unwrap()
+            .clone();
+        values.sort_by_key(|value| format!("{:?}", value));
+        assert_eq!(
+            values,
+            vec![MeasurementValue::Bool(false), MeasurementValue::Bool(true)]
+        );
+    }
+
+    #[test]
+    fn malformed_measurements_skipped() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:b .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:c .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                _:b <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#titleAvailability> .
+                _:c <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#descriptionAvailability> .
+                _:c <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#,
+            )
+            .unwrap();
+
+        let measurements = first_measurement_values(graph.quality_measurements().unwrap());
+        assert_eq!(
+            measurements,
+            HashMap::from([
+                (
+                    (
+                        node("https://dataset.assessment.foo"),
+                        mqa_node("keywordAvailability")
+                    ),
+                    MeasurementValue::Bool(true)
+                ),
+                (
+                    (
+                        node("https://dataset.assessment.foo"),
+                        mqa_node("descriptionAvailability")
+                    ),
+                    MeasurementValue::Bool(false)
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_node_measurements_skipped() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:b .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#keywordAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                _:b <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#titleAvailability> .
+                _:b <http://www.w3.org/ns/dqv#value> <https://not.a.literal> .
+                "#,
+            )
+            .unwrap();
+
+        let measurements = graph
+            .measurements_for(node("https://dataset.assessment.foo").as_ref())
+            .unwrap();
+        assert_eq!(
+            measurements,
+            HashMap::from([(
+                mqa_node("keywordAvailability"),
+                vec![MeasurementValue::Bool(true)]
+            )])
+        );
+    }
+
     #[test]
     fn modification_timestamp() {
         let graph = measurement_graph();
@@ -513,4 +1393,46 @@ mod tests {
         assert!(graph.to_turtle().unwrap().contains("<https://dataset.assessment.foo> <http://purl.org/dc/terms/modified> \"2022-06-27 08:01:52.123 +0000\"^^<http://www.w3.org/2001/XMLSchema#dateTime> ."));
         assert_eq!(graph.get_modified_timestmap().unwrap(), 1656316912123);
     }
+
+    #[test]
+    fn load_from_reader() {
+        let graph = AssessmentGraph::new().unwrap();
+        graph
+            .load_reader(std::io::BufReader::new(MEASUREMENT_GRAPH.as_bytes()))
+            .unwrap();
+        assert_eq!(graph.distributions().unwrap().len(), 2);
+        assert_eq!(
+            canonical_triples(&graph.to_turtle().unwrap()).unwrap(),
+            canonical_triples(MEASUREMENT_GRAPH).unwrap()
+        );
+    }
+
+    #[test]
+    fn prefixed_turtle() {
+        let graph = measurement_graph();
+        let prefixed = graph.to_prefixed_turtle().unwrap();
+
+        assert!(prefixed.contains("@prefix dqv: <http://www.w3.org/ns/dqv#>"));
+        assert!(prefixed.contains("dqv:value"));
+        assert!(!prefixed.contains("<http://www.w3.org/ns/dqv#value>"));
+        assert_eq!(
+            canonical_triples(&prefixed).unwrap(),
+            canonical_triples(&graph.to_turtle().unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn provenance() {
+        let graph = measurement_graph();
+        graph.insert_provenance(Utc::now()).unwrap();
+        graph.insert_provenance(Utc::now()).unwrap();
+
+        let turtle = graph.to_turtle().unwrap();
+        let version = format!("\"fdk-mqa-scoring-service/{}\"", env!("CARGO_PKG_VERSION"));
+        assert_eq!(turtle.matches(&version).count(), 1);
+        assert_eq!(
+            turtle.matches("http://purl.org/dc/terms/created").count(),
+            1
+        );
+    }
 }
@@ -1,10 +1,17 @@
-use actix_web::{get, App, HttpServer, Responder};
+use std::{env, fs, iter, sync::Arc};
+
+use actix_web::{
+    get, http::header::ACCEPT, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder,
+};
+use arc_swap::ArcSwap;
 use fdk_mqa_scoring_service::{
-    kafka::{
-        create_sr_settings, run_async_processor, BROKERS, INPUT_TOPIC, SCHEMA_REGISTRY,
-        SCORING_API_URL,
-    },
-    metrics::{get_metrics, register_metrics},
+    assessment_graph::AssessmentGraph,
+    error::Error,
+    json_conversion::{convert_definitions, convert_scores},
+    kafka::{create_sr_settings, run_async_processor, run_lag_reporter, CONFIG},
+    metrics::{get_metrics_as, register_metrics, MetricsFormat},
+    score::calculate_score,
+    score_graph::{load_score_definitions, reload_score_definitions, SharedScoreDefinitions},
 };
 use futures::{
     stream::{FuturesUnordered, StreamExt},
@@ -22,19 +29,100 @@ async fn ready() -> impl Responder {
 }
 
 #[get("/metrics")]
-async fn metrics() -> impl Responder {
-    match get_metrics() {
-        Ok(metrics) => metrics,
+async fn metrics(request: HttpRequest) -> impl Responder {
+    let format = MetricsFormat::from_accept(
+        request
+            .headers()
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let metrics = get_metrics_as(format).unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "unable to gather metrics");
+        "".to_string()
+    });
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .body(metrics)
+}
+
+/// Scoring methodology of the score definitions in use.
+#[get("/definitions")]
+async fn definitions(score_definitions: web::Data<SharedScoreDefinitions>) -> impl Responder {
+    HttpResponse::Ok().json(convert_definitions(&score_definitions.load()))
+}
+
+#[post("/reload-scores")]
+async fn reload_scores(
+    request: HttpRequest,
+    score_definitions: web::Data<SharedScoreDefinitions>,
+) -> impl Responder {
+    let api_key = request
+        .headers()
+        .get("X-API-KEY")
+        .and_then(|value| value.to_str().ok());
+    if CONFIG.scoring_api_key.is_empty() || api_key != Some(CONFIG.scoring_api_key.as_str()) {
+        return HttpResponse::Unauthorized().finish();
+    }
+
+    // Bundled score values can't change while running, there is nothing to reload.
+    let Some(score_values_file) = CONFIG.score_values_file.as_deref() else {
+        return HttpResponse::BadRequest().body("SCORE_VALUES_FILE is not set");
+    };
+
+    match reload_score_definitions(
+        &score_definitions,
+        score_values_file,
+        CONFIG.dimension_overrides_file.as_deref(),
+    ) {
+        Ok(total_score) => {
+            tracing::info!(total_score, "reloaded score definitions");
+            HttpResponse::Ok().json(serde_json::json!({ "total_score": total_score }))
+        }
         Err(e) => {
-            tracing::error!(error = e.to_string(), "unable to gather metrics");
-            "".to_string()
+            tracing::error!(error = e.to_string(), "unable to reload score definitions");
+            HttpResponse::InternalServerError().body(e.to_string())
         }
     }
 }
 
+/// Scores a local Turtle assessment file and prints the scores JSON and scored Turtle.
+fn score_file(path: &str) -> Result<(), Error> {
+    let score_definitions = load_score_definitions(
+        CONFIG.score_values_file.as_deref(),
+        CONFIG.dimension_overrides_file.as_deref(),
+    )?;
+    let assessment_graph = AssessmentGraph::new()?;
+    assessment_graph.load(fs::read_to_string(path)?)?;
+
+    let (dataset_score, distribution_scores) =
+        calculate_score(&assessment_graph, &score_definitions)?;
+    let node_scores: Vec<_> = iter::once(dataset_score)
+        .chain(distribution_scores)
+        .collect();
+    let scores = convert_scores(&score_definitions, &node_scores)?;
+
+    assessment_graph.insert_scores(&node_scores)?;
+
+    println!("{}", serde_json::to_string_pretty(&scores)?);
+    println!("{}", assessment_graph.to_turtle()?);
+    Ok(())
+}
+
 #[tokio::main]
-async fn main() {    
-    
+async fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(String::as_str) == Some("score-file") {
+        let Some(path) = args.get(2) else {
+            eprintln!("usage: {} score-file <path>", args[0]);
+            std::process::exit(2);
+        };
+        score_file(path).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        });
+        return;
+    }
+
     tracing_subscriber::fmt()
         .json()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -47,31 +135,63 @@ async fn main() {
     register_metrics();
 
     tracing::info!(
-        brokers = BROKERS.to_string(),
-        schema_registry = SCHEMA_REGISTRY.to_string(),
-        input_topic = INPUT_TOPIC.to_string(),
-        scoring_api_url = SCORING_API_URL.to_string(),
+        brokers = CONFIG.brokers,
+        schema_registry = CONFIG.schema_registry,
+        consumer_group_id = CONFIG.consumer_group_id,
+        input_topic = CONFIG.input_topics.join(","),
+        scoring_api_url = CONFIG.scoring_api_url,
+        scoring_api_path = CONFIG.scoring_api_path,
+        num_workers = CONFIG.num_workers,
         "starting service"
     );
 
-    let sr_settings = create_sr_settings().unwrap_or_else(|e| {
+    let sr_settings = create_sr_settings(&CONFIG).unwrap_or_else(|e| {
         tracing::error!(error = e.to_string(), "sr settings creation error");
         std::process::exit(1);
     });
 
+    let score_definitions: SharedScoreDefinitions = Arc::new(ArcSwap::from_pointee(
+        load_score_definitions(
+            CONFIG.score_values_file.as_deref(),
+            CONFIG.dimension_overrides_file.as_deref(),
+        )
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "score definitions error");
+            std::process::exit(1);
+        }),
+    ));
+
+    let app_score_definitions = web::Data::new(score_definitions.clone());
     let http_server = tokio::spawn(
-        HttpServer::new(|| App::new().service(ping).service(ready).service(metrics))
-            .bind(("0.0.0.0", 8080))
-            .unwrap_or_else(|e| {
-                tracing::error!(error = e.to_string(), "metrics server error");
-                std::process::exit(1);
-            })
-            .run()
-            .map(|f| f.map_err(|e| e.into())),
+        HttpServer::new(move || {
+            App::new()
+                .app_data(app_score_definitions.clone())
+                .service(ping)
+                .service(ready)
+                .service(metrics)
+                .service(definitions)
+                .service(reload_scores)
+        })
+        .bind(("0.0.0.0", 8080))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "metrics server error");
+            std::process::exit(1);
+        })
+        .run()
+        .map(|f| f.map_err(|e| e.into())),
     );
 
-    (0..4)
-        .map(|i| tokio::spawn(run_async_processor(i, sr_settings.clone())))
+    (0..CONFIG.num_workers)
+        .map(|i| {
+            tokio::spawn(run_async_processor(
+                i,
+                &CONFIG,
+                sr_settings.clone(),
+                score_definitions.clone(),
+                None,
+            ))
+        })
+        .chain(std::iter::once(tokio::spawn(run_lag_reporter(&CONFIG))))
         .chain(std::iter::once(http_server))
         .collect::<FuturesUnordered<_>>()
         .for_each(|result| async {
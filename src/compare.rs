@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use oxigraph::{
+    io::{RdfFormat, RdfParser},
+    model::{BlankNode, Subject, Term, Triple},
+};
+
+use crate::error::Error;
+
+/// Parses turtle into sorted N-Triples lines, with blank nodes relabelled canonically,
+/// so that logically equal graphs compare equal.
+/// Blank nodes are labelled by the triples they occur in, which distinguishes them
+/// as long as no two blank nodes have identical neighbourhoods.
+pub fn canonical_triples(turtle: &str) -> Result<Vec<String>, Error> {
+    let triples = RdfParser::from_format(RdfFormat::Turtle)
+        .for_reader(turtle.as_bytes())
+        .map(|quad| quad.map(Triple::from))
+        .collect::<Result<Vec<Triple>, _>>()
+        .map_err(|e| format!("unable to parse turtle: {e}"))?;
+
+    let mut signatures: HashMap<BlankNode, Vec<String>> = HashMap::new();
+    for triple in triples.iter() {
+        for node in blank_nodes(triple) {
+            signatures
+                .entry(node.clone())
+                .or_default()
+                .push(render(triple, &|other| {
+                    if other == node {
+                        "_:self".to_string()
+                    } else {
+                        "_:other".to_string()
+                    }
+                }));
+        }
+    }
+
+    let mut nodes = signatures
+        .into_iter()
+        .map(|(node, mut signature)| {
+            signature.sort();
+            (signature, node)
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let labels = nodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, node))| (node, format!("_:c{i}")))
+        .collect::<HashMap<_, _>>();
+
+    let mut canonical = triples
+        .iter()
+        .map(|triple| render(triple, &|node| labels[node].clone()))
+        .collect::<Vec<_>>();
+    canonical.sort();
+    canonical.dedup();
+    Ok(canonical)
+}
+
+/// Blank nodes in subject or object position of a triple.
+fn blank_nodes(triple: &Triple) -> Vec<&BlankNode> {
+    let mut nodes = Vec::new();
+    if let Subject::BlankNode(node) = &triple.subject {
+        nodes.push(node);
+    }
+    if let Term::BlankNode(node) = &triple.object {
+        nodes.push(node);
+    }
+    nodes
+}
+
+/// Renders triple as N-Triples, labelling blank nodes with `label`.
+fn render(triple: &Triple, label: &dyn Fn(&BlankNode) -> String) -> String {
+    let subject = match &triple.subject {
+        Subject::BlankNode(node) => label(node),
+        subject => subject.to_string(),
+    };
+    let object = match &triple.object {
+        Term::BlankNode(node) => label(node),
+        object => object.to_string(),
+    };
+    format!("{} {} {} .", subject, triple.predicate, object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_node_labels() {
+        let a = canonical_triples(
+            r#"
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:x .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:y .
+            _:x <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            _:y <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            "#,
+        )
+        .unwrap();
+        let b = canonical_triples(
+            r#"
+            _:m2 <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            _:m1 <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:m1 .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:m2 .
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(a.len(), 4);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_graphs() {
+        let a = canonical_triples(r#"_:x <http://www.w3.org/ns/dqv#value> "true" ."#).unwrap();
+        let b = canonical_triples(r#"_:x <http://www.w3.org/ns/dqv#value> "false" ."#).unwrap();
+        assert_ne!(a, b);
+    }
+}
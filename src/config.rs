@@ -0,0 +1,516 @@
+use std::{env, fmt::Display, fs, str::FromStr, time::Duration};
+
+use crate::{
+    error::Error,
+    json_conversion::Rounding,
+    schemas::MqaEventType,
+    score::{Aggregation, MissingMeasurements},
+};
+
+/// Service configuration, read from environment variables and validated once at startup.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub brokers: String,
+    pub schema_registry: String,
+    pub consumer_group_id: String,
+    pub input_topics: Vec<String>,
+    pub scoring_api_url: String,
+    pub scoring_api_path: String,
+    pub scoring_api_key: String,
+    pub num_workers: usize,
+    pub request_timeout: Duration,
+    pub sink: Sink,
+    pub output_topic: Option<String>,
+    pub aggregation: Aggregation,
+    /// Score values replacing the bundled ones, if set.
+    pub score_values_file: Option<String>,
+    pub dimension_overrides_file: Option<String>,
+    /// Event types that are scored, others are skipped.
+    pub process_event_types: Vec<MqaEventType>,
+    /// Full avro names, as (namespace, name), of legacy dataset events accepted as mqa events.
+    /// None are accepted unless configured.
+    pub legacy_events: Vec<(String, String)>,
+    /// Whether events of unknown type fail rather than being skipped.
+    pub strict_event_types: bool,
+    /// Whether datasets not matching the FDK ID of the event fail rather than being logged.
+    pub strict_dataset_id: bool,
+    pub halt_on_error: bool,
+    pub commit_mode: OffsetCommitMode,
+    pub max_payload_bytes: usize,
+    pub max_clock_skew_secs: i64,
+    pub validate_iris: bool,
+    pub emit_jsonld: bool,
+    pub prefixed_turtle: bool,
+    pub percentage_rounding: Option<Rounding>,
+    pub missing_measurements: MissingMeasurements,
+    pub omit_unscored: bool,
+    pub log_raw_payload: bool,
+}
+
+/// Where computed scores are sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Sink {
+    /// Posted to the scoring api.
+    #[default]
+    Api,
+    /// Produced as score events to `OUTPUT_TOPIC`. Prior assessments are not read from the
+    /// scoring api, so events must carry complete assessments.
+    Kafka,
+    /// Posted to the scoring api, then produced as score events.
+    Both,
+}
+
+impl Sink {
+    pub fn posts_to_api(&self) -> bool {
+        matches!(self, Self::Api | Self::Both)
+    }
+
+    pub fn produces_to_kafka(&self) -> bool {
+        matches!(self, Self::Kafka | Self::Both)
+    }
+}
+
+impl TryFrom<&str> for Sink {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "api" => Ok(Self::Api),
+            "kafka" => Ok(Self::Kafka),
+            "both" => Ok(Self::Both),
+            _ => Err(format!("invalid SINK '{value}', expected 'api', 'kafka' or 'both'").into()),
+        }
+    }
+}
+
+impl Config {
+    /// Reads configuration from environment variables.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    /// Reads configuration from variables provided by `lookup`.
+    /// Errors name the variable that is missing or invalid.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let var = |name: &str, default: &str| lookup(name).unwrap_or(default.to_string());
+
+        let input_topics = parse_topics(&var("INPUT_TOPIC", "mqa-events"));
+        if input_topics.is_empty() {
+            return Err("missing INPUT_TOPIC, no topic names given".into());
+        }
+        let consumer_group_id = var("CONSUMER_GROUP_ID", "fdk-mqa-scoring-service");
+        if consumer_group_id.trim().is_empty() {
+            return Err("invalid CONSUMER_GROUP_ID, no group id given".into());
+        }
+        let scoring_api_path = var("SCORING_API_PATH", "/api/assessments/{id}");
+        validate_path_template(&scoring_api_path)
+            .map_err(|e| format!("invalid SCORING_API_PATH: {e}"))?;
+        let scoring_api_key = read_api_key(lookup("API_KEY_FILE"), lookup("API_KEY"))
+            .map_err(|e| format!("invalid API_KEY_FILE: {e}"))?;
+        let request_timeout = Duration::from_secs(
+            parse_var("REQUEST_TIMEOUT_SECS", lookup("REQUEST_TIMEOUT_SECS"))?.unwrap_or(30),
+        );
+
+        let sink = Sink::try_from(var("SINK", "api").as_str())?;
+        let output_topic = lookup("OUTPUT_TOPIC").filter(|topic| !topic.trim().is_empty());
+        if sink.produces_to_kafka() && output_topic.is_none() {
+            return Err(format!("missing OUTPUT_TOPIC, required by SINK '{sink:?}'").into());
+        }
+        let aggregation = Aggregation::try_from(var("AGGREGATION", "best-distribution").as_str())?;
+
+        let process_event_types = parse_event_types(&var(
+            "PROCESS_EVENT_TYPES",
+            "PROPERTIES_CHECKED,URLS_CHECKED,DCAT_COMPLIANCE_CHECKED",
+        ))
+        .map_err(|e| format!("invalid PROCESS_EVENT_TYPES: {e}"))?;
+        let percentage_rounding = lookup("PERCENTAGE_ROUNDING")
+            .map(|value| Rounding::try_from(value.as_str()))
+            .transpose()
+            .map_err(|e| format!("invalid PERCENTAGE_ROUNDING: {e}"))?;
+        let missing_measurements = lookup("MISSING_MEASUREMENTS")
+            .map(|value| MissingMeasurements::try_from(value.as_str()))
+            .transpose()
+            .map_err(|e| format!("invalid MISSING_MEASUREMENTS: {e}"))?
+            .unwrap_or_default();
+
+        Ok(Self {
+            brokers: var("BROKERS", "localhost:9092"),
+            schema_registry: var("SCHEMA_REGISTRY", "http://localhost:8081"),
+            consumer_group_id,
+            input_topics,
+            scoring_api_url: var("SCORING_API_URL", "http://localhost:8082"),
+            scoring_api_path,
+            scoring_api_key,
+            num_workers: parse_num_workers(lookup("NUM_WORKERS"))?,
+            request_timeout,
+            sink,
+            output_topic,
+            aggregation,
+            score_values_file: lookup("SCORE_VALUES_FILE"),
+            dimension_overrides_file: lookup("DIMENSION_OVERRIDES_FILE"),
+            process_event_types,
+            legacy_events: parse_event_names(&var("LEGACY_EVENTS", "")),
+            strict_event_types: parse_var("STRICT_EVENT_TYPES", lookup("STRICT_EVENT_TYPES"))?
+                .unwrap_or(false),
+            strict_dataset_id: parse_var("STRICT_DATASET_ID", lookup("STRICT_DATASET_ID"))?
+                .unwrap_or(false),
+            halt_on_error: parse_var("HALT_ON_ERROR", lookup("HALT_ON_ERROR"))?.unwrap_or(false),
+            commit_mode: parse_commit_mode(lookup("COMMIT_MODE"))?,
+            max_payload_bytes: parse_var("MAX_PAYLOAD_BYTES", lookup("MAX_PAYLOAD_BYTES"))?
+                .unwrap_or(8 * 1024 * 1024),
+            max_clock_skew_secs: parse_var("MAX_CLOCK_SKEW_SECS", lookup("MAX_CLOCK_SKEW_SECS"))?
+                .unwrap_or(24 * 60 * 60),
+            validate_iris: parse_var("VALIDATE_IRIS", lookup("VALIDATE_IRIS"))?.unwrap_or(false),
+            emit_jsonld: parse_var("EMIT_JSONLD", lookup("EMIT_JSONLD"))?.unwrap_or(true),
+            prefixed_turtle: parse_var("PREFIXED_TURTLE", lookup("PREFIXED_TURTLE"))?
+                .unwrap_or(false),
+            percentage_rounding,
+            missing_measurements,
+            omit_unscored: parse_var("OMIT_UNSCORED", lookup("OMIT_UNSCORED"))?.unwrap_or(false),
+            log_raw_payload: parse_var("LOG_RAW_PAYLOAD", lookup("LOG_RAW_PAYLOAD"))?
+                .unwrap_or(false),
+        })
+    }
+}
+
+/// Parses variable `name`, if set, e.g. a number or a `true`/`false` flag.
+fn parse_var<T>(name: &str, value: Option<String>) -> Result<Option<T>, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|e| format!("unable to parse {name} '{value}': {e}").into())
+        })
+        .transpose()
+}
+
+/// Reads the scoring api key from file, if a path is given, falling back to the key itself.
+fn read_api_key(key_file: Option<String>, key: Option<String>) -> Result<String, Error> {
+    match key_file {
+        Some(path) => fs::read_to_string(&path)
+            .map(|key| key.trim_end_matches(['\r', '\n']).to_string())
+            .map_err(|e| format!("unable to read api key file '{path}': {e}").into()),
+        None => Ok(key.unwrap_or_default()),
+    }
+}
+
+/// Parses comma separated full avro names into (namespace, name) pairs.
+fn parse_event_names(names: &str) -> Vec<(String, String)> {
+    names
+        .split(",")
+        .filter_map(|name| name.trim().rsplit_once("."))
+        .map(|(namespace, name)| (namespace.to_string(), name.to_string()))
+        .collect()
+}
+
+/// Parses comma separated topic names.
+fn parse_topics(topics: &str) -> Vec<String> {
+    topics
+        .split(",")
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses number of worker tasks, defaulting to 4.
+fn parse_num_workers(value: Option<String>) -> Result<usize, Error> {
+    match value {
+        None => Ok(4),
+        Some(value) => match value.parse::<usize>() {
+            Ok(0) => Err("NUM_WORKERS must be at least 1".into()),
+            Ok(num_workers) => Ok(num_workers),
+            Err(e) => Err(format!("unable to parse NUM_WORKERS '{value}': {e}").into()),
+        },
+    }
+}
+
+/// How offsets of handled messages are committed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OffsetCommitMode {
+    /// Store offset, leaving commit to auto-commit.
+    Store,
+    /// Commit offset synchronously.
+    Sync,
+    /// Commit offset asynchronously.
+    Async,
+}
+
+/// Parses offset commit mode, defaulting to store.
+fn parse_commit_mode(value: Option<String>) -> Result<OffsetCommitMode, Error> {
+    match value.as_deref() {
+        None | Some("store") => Ok(OffsetCommitMode::Store),
+        Some("sync") => Ok(OffsetCommitMode::Sync),
+        Some("async") => Ok(OffsetCommitMode::Async),
+        Some(value) => Err(format!(
+            "unknown COMMIT_MODE '{value}', expected one of: store, sync, async"
+        )
+        .into()),
+    }
+}
+
+/// Parses comma separated event types, as named in the avro schema.
+fn parse_event_types(types: &str) -> Result<Vec<MqaEventType>, Error> {
+    types
+        .split(",")
+        .map(|event_type| match event_type.trim() {
+            "PROPERTIES_CHECKED" => Ok(MqaEventType::PropertiesChecked),
+            "URLS_CHECKED" => Ok(MqaEventType::UrlsChecked),
+            "DCAT_COMPLIANCE_CHECKED" => Ok(MqaEventType::DcatComplienceChecked),
+            other => Err(format!("unknown event type '{other}'").into()),
+        })
+        .collect()
+}
+
+/// Validates that a scoring api path template contains the `{id}` placeholder.
+pub fn validate_path_template(template: &str) -> Result<(), Error> {
+    if template.contains("{id}") {
+        Ok(())
+    } else {
+        Err(
+            format!("scoring api path template '{template}' is missing '{{id}}' placeholder")
+                .into(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<Config, Error> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Config::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn defaults() {
+        let config = config(&[]).unwrap();
+        assert_eq!(config.brokers, "localhost:9092");
+        assert_eq!(config.consumer_group_id, "fdk-mqa-scoring-service");
+        assert_eq!(config.input_topics, vec!["mqa-events"]);
+        assert_eq!(config.scoring_api_key, "");
+        assert_eq!(config.num_workers, 4);
+        assert_eq!(config.request_timeout, Duration::from_secs(30));
+        assert_eq!(config.sink, Sink::Api);
+        assert_eq!(config.output_topic, None);
+        assert_eq!(config.aggregation, Aggregation::BestDistribution);
+        assert_eq!(config.score_values_file, None);
+        assert_eq!(
+            config.process_event_types,
+            vec![
+                MqaEventType::PropertiesChecked,
+                MqaEventType::UrlsChecked,
+                MqaEventType::DcatComplienceChecked,
+            ]
+        );
+        assert_eq!(config.legacy_events, vec![]);
+        assert_eq!(config.commit_mode, OffsetCommitMode::Store);
+        assert_eq!(config.max_payload_bytes, 8 * 1024 * 1024);
+        assert_eq!(config.max_clock_skew_secs, 24 * 60 * 60);
+        assert!(config.emit_jsonld);
+        assert!(!config.halt_on_error && !config.validate_iris && !config.omit_unscored);
+        assert_eq!(config.percentage_rounding, None);
+        assert_eq!(config.missing_measurements, MissingMeasurements::default());
+    }
+
+    #[test]
+    fn configured() {
+        let config = config(&[
+            ("BROKERS", "kafka:9092"),
+            ("CONSUMER_GROUP_ID", "fdk-mqa-scoring-service-shadow"),
+            ("INPUT_TOPIC", "a, b"),
+            ("API_KEY", "key"),
+            ("NUM_WORKERS", "2"),
+            ("REQUEST_TIMEOUT_SECS", "5"),
+            ("SINK", "both"),
+            ("OUTPUT_TOPIC", "mqa-scores"),
+            ("AGGREGATION", "union-max"),
+            ("SCORE_VALUES_FILE", "/etc/mqa/scores.ttl"),
+            ("PROCESS_EVENT_TYPES", "URLS_CHECKED"),
+            ("COMMIT_MODE", "sync"),
+            ("MAX_PAYLOAD_BYTES", "1024"),
+            ("MAX_CLOCK_SKEW_SECS", "60"),
+            ("EMIT_JSONLD", "false"),
+            ("HALT_ON_ERROR", "true"),
+            ("PERCENTAGE_ROUNDING", "floor"),
+        ])
+        .unwrap();
+        assert_eq!(config.brokers, "kafka:9092");
+        assert_eq!(config.consumer_group_id, "fdk-mqa-scoring-service-shadow");
+        assert_eq!(config.input_topics, vec!["a", "b"]);
+        assert_eq!(config.scoring_api_key, "key");
+        assert_eq!(config.num_workers, 2);
+        assert_eq!(config.request_timeout, Duration::from_secs(5));
+        assert!(config.sink.posts_to_api() && config.sink.produces_to_kafka());
+        assert_eq!(config.output_topic, Some("mqa-scores".to_string()));
+        assert_eq!(config.aggregation, Aggregation::UnionMax);
+        assert_eq!(
+            config.score_values_file,
+            Some("/etc/mqa/scores.ttl".to_string())
+        );
+        assert_eq!(config.process_event_types, vec![MqaEventType::UrlsChecked]);
+        assert_eq!(config.commit_mode, OffsetCommitMode::Sync);
+        assert_eq!(config.max_payload_bytes, 1024);
+        assert_eq!(config.max_clock_skew_secs, 60);
+        assert!(!config.emit_jsonld);
+        assert!(config.halt_on_error);
+        assert_eq!(config.percentage_rounding, Some(Rounding::Floor));
+    }
+
+    #[test]
+    fn missing_vars() {
+        let err = config(&[("INPUT_TOPIC", " ,")]).unwrap_err();
+        assert!(err.to_string().contains("missing INPUT_TOPIC"));
+
+        let err = config(&[("CONSUMER_GROUP_ID", "")]).unwrap_err();
+        assert!(err.to_string().contains("invalid CONSUMER_GROUP_ID"));
+
+        let err = config(&[("SINK", "kafka")]).unwrap_err();
+        assert!(err.to_string().contains("missing OUTPUT_TOPIC"));
+
+        let err = config(&[("API_KEY_FILE", "/nonexistent/api-key")]).unwrap_err();
+        assert!(err.to_string().contains("invalid API_KEY_FILE"));
+    }
+
+    #[test]
+    fn malformed_vars() {
+        let err = config(&[("NUM_WORKERS", "four")]).unwrap_err();
+        assert!(err.to_string().contains("NUM_WORKERS"));
+
+        let err = config(&[("REQUEST_TIMEOUT_SECS", "-1")]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unable to parse REQUEST_TIMEOUT_SECS '-1'"));
+
+        let err = config(&[("SINK", "file")]).unwrap_err();
+        assert!(err.to_string().contains("invalid SINK 'file'"));
+
+        let err = config(&[("AGGREGATION", "best")]).unwrap_err();
+        assert!(err.to_string().contains("invalid AGGREGATION 'best'"));
+
+        // Malformed values fail rather than falling back to defaults.
+        let err = config(&[("MAX_PAYLOAD_BYTES", "8MB")]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unable to parse MAX_PAYLOAD_BYTES '8MB'"));
+
+        let err = config(&[("MAX_CLOCK_SKEW_SECS", "1d")]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unable to parse MAX_CLOCK_SKEW_SECS '1d'"));
+
+        let err = config(&[("HALT_ON_ERROR", "yes")]).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unable to parse HALT_ON_ERROR 'yes'"));
+
+        let err = config(&[("COMMIT_MODE", "flush")]).unwrap_err();
+        assert!(err.to_string().contains("COMMIT_MODE 'flush'"));
+
+        let err = config(&[("PROCESS_EVENT_TYPES", "FOO")]).unwrap_err();
+        assert!(err.to_string().contains("invalid PROCESS_EVENT_TYPES"));
+
+        let err = config(&[("PERCENTAGE_ROUNDING", "ceil")]).unwrap_err();
+        assert!(err.to_string().contains("invalid PERCENTAGE_ROUNDING"));
+
+        let err = config(&[("MISSING_MEASUREMENTS", "skip")]).unwrap_err();
+        assert!(err.to_string().contains("invalid MISSING_MEASUREMENTS"));
+
+        let err = config(&[("SCORING_API_PATH", "/api/assessments")]).unwrap_err();
+        assert!(err.to_string().contains("invalid SCORING_API_PATH"));
+    }
+
+    #[test]
+    fn topics() {
+        assert_eq!(parse_topics("mqa-events"), vec!["mqa-events"]);
+        assert_eq!(
+            parse_topics("mqa-events, mqa-events-mirror,"),
+            vec!["mqa-events", "mqa-events-mirror"]
+        );
+        assert!(parse_topics("").is_empty());
+    }
+
+    #[test]
+    fn api_key_file_precedence() {
+        let path = std::env::temp_dir().join(format!("api-key-{}", Uuid::new_v4()));
+        fs::write(&path, "file-key\n").unwrap();
+
+        assert_eq!(
+            read_api_key(
+                Some(path.to_string_lossy().to_string()),
+                Some("env-key".to_string())
+            )
+            .unwrap(),
+            "file-key"
+        );
+        assert_eq!(
+            read_api_key(None, Some("env-key".to_string())).unwrap(),
+            "env-key"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn missing_api_key_file() {
+        let err = read_api_key(Some("/nonexistent/api-key".to_string()), None)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unable to read api key file '/nonexistent/api-key'"));
+    }
+
+    #[test]
+    fn num_workers() {
+        assert_eq!(parse_num_workers(None).unwrap(), 4);
+        assert_eq!(parse_num_workers(Some("8".to_string())).unwrap(), 8);
+        assert!(parse_num_workers(Some("0".to_string())).is_err());
+        assert!(parse_num_workers(Some("four".to_string())).is_err());
+    }
+
+    #[test]
+    fn event_types() {
+        assert_eq!(
+            parse_event_types("URLS_CHECKED, PROPERTIES_CHECKED").unwrap(),
+            vec![MqaEventType::UrlsChecked, MqaEventType::PropertiesChecked]
+        );
+        assert!(parse_event_types("URLS_CHECKED,FOO").is_err());
+    }
+
+    #[test]
+    fn event_names() {
+        assert_eq!(
+            parse_event_names("no.fdk.dataset.DatasetEvent, no.fdk.other.Event"),
+            vec![
+                ("no.fdk.dataset".to_string(), "DatasetEvent".to_string()),
+                ("no.fdk.other".to_string(), "Event".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_template_missing_placeholder() {
+        assert!(validate_path_template("/gateway/scoring/{id}/assessment").is_ok());
+        assert!(validate_path_template("/api/assessments/").is_err());
+    }
+
+    #[test]
+    fn commit_mode() {
+        assert_eq!(parse_commit_mode(None).unwrap(), OffsetCommitMode::Store);
+        assert_eq!(
+            parse_commit_mode(Some("async".to_string())).unwrap(),
+            OffsetCommitMode::Async
+        );
+        assert!(parse_commit_mode(Some("flush".to_string())).is_err());
+    }
+}
@@ -30,6 +30,16 @@ pub enum Error {
     SRCError(#[from] schema_registry_converter::error::SRCError),
     #[error("{0}")]
     String(String),
+    /// Failure that retrying will not resolve.
+    #[error("{0}")]
+    Permanent(String),
+}
+
+impl Error {
+    /// Whether retrying the failed operation is pointless.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, Self::Permanent(_))
+    }
 }
 
 impl From<&str> for Error {
@@ -2,7 +2,7 @@ use std::fs;
 
 use oxigraph::{
     io::{RdfFormat, RdfParser},
-    model::{GraphNameRef, NamedNode, Quad, Subject, Term},
+    model::{GraphNameRef, NamedNode, NamedNodeRef, NamedOrBlankNode, Quad, Subject, Term},
     sparql::{QueryResults, QuerySolution},
     store::{StorageError, Store},
 };
@@ -17,6 +17,20 @@ pub fn execute_query(store: &Store, q: &str) -> Result<Vec<QuerySolution>, Error
     }
 }
 
+// Renders named node as SPARQL IRI term, rejecting IRIs that would break out of `<...>`.
+pub fn sparql_iri(node: NamedNodeRef) -> Result<String, Error> {
+    let iri = node.as_str();
+    match iri
+        .chars()
+        .find(|c| *c <= ' ' || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\'))
+    {
+        Some(c) => {
+            Err(format!("IRI '{iri}' contains character '{c}', not allowed in SPARQL").into())
+        }
+        None => Ok(format!("<{iri}>")),
+    }
+}
+
 // Loads files from a list of filenames.
 pub fn load_files(fnames: Vec<&str>) -> Result<Vec<String>, Error> {
     fnames
@@ -33,7 +47,7 @@ pub fn parse_graphs<G: ToString>(graphs: Vec<G>) -> Result<Store, Error> {
             RdfParser::from_format(RdfFormat::Turtle)
                 .without_named_graphs()
                 .with_default_graph(GraphNameRef::DefaultGraph),
-            graph.to_string().as_bytes().as_ref()
+            graph.to_string().as_bytes(),
         )?;
     }
     Ok(store)
@@ -54,3 +68,31 @@ pub fn named_quad_object(result: Result<Quad, StorageError>) -> Result<NamedNode
         _ => Err("unable to get named quad object".into()),
     }
 }
+
+// Attemts to extract quad object as named or blank node.
+pub fn named_or_blank_quad_object(
+    result: Result<Quad, StorageError>,
+) -> Result<NamedOrBlankNode, Error> {
+    match result?.object {
+        Term::NamedNode(node) => Ok(node.into()),
+        Term::BlankNode(node) => Ok(node.into()),
+        _ => Err("unable to get named or blank quad object".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparql_iri_escaping() {
+        assert_eq!(
+            sparql_iri(NamedNodeRef::new_unchecked("https://dataset.foo#a")).unwrap(),
+            "<https://dataset.foo#a>"
+        );
+        assert!(sparql_iri(NamedNodeRef::new_unchecked(
+            "https://dataset.foo> ?p ?o } DELETE WHERE { ?s ?p ?o"
+        ))
+        .is_err());
+    }
+}
@@ -1,29 +1,128 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::Utc;
+use oxigraph::model::NamedNode;
 use serde::{Deserialize, Serialize};
 
-use crate::{score, score_graph::ScoreDefinitions};
+use crate::{
+    assessment_graph::AssessmentGraph,
+    error::Error,
+    score,
+    score_graph::{default_score_definitions, ScoreDefinitions},
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateRequest {
     pub turtle_assessment: String,
     pub jsonld_assessment: String,
     pub scores: Scores,
+    /// Scores only contain dimensions changed since the previous scoring.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+impl UpdateRequest {
+    /// Coalesces two updates of the same dataset into one, e.g. in a batch run.
+    /// Measurements are unioned, deduplicating identical triples, and each metric gets the max
+    /// score of the two. The scores, the latest modification timestamp and new provenance
+    /// replace those of either assessment. Json-ld is regenerated from the merged turtle,
+    /// unless neither has any.
+    pub fn merge(self, other: UpdateRequest) -> Result<UpdateRequest, Error> {
+        let modified = modified_timestamp(&self.turtle_assessment)?
+            .max(modified_timestamp(&other.turtle_assessment)?);
+        let scores = self.scores.merge(&other.scores);
+
+        let graph = AssessmentGraph::new()?;
+        graph.load_distinct(&self.turtle_assessment)?;
+        graph.load_distinct(&other.turtle_assessment)?;
+        graph.remove_scores()?;
+        graph.insert_scores(&scores.node_scores(&graph)?)?;
+        if let Some(modified) = modified {
+            graph.insert_modified_timestmap(modified)?;
+        }
+        graph.insert_provenance(Utc::now())?;
+        let turtle_assessment = graph.to_turtle()?;
+        let jsonld_assessment =
+            if self.jsonld_assessment.is_empty() && other.jsonld_assessment.is_empty() {
+                String::new()
+            } else {
+                graph.turtle_to_jsonld(&turtle_assessment)?
+            };
+
+        Ok(UpdateRequest {
+            turtle_assessment,
+            jsonld_assessment,
+            scores,
+            partial: self.partial && other.partial,
+        })
+    }
+}
+
+/// Modification timestamp of an assessment, if it has any.
+fn modified_timestamp(turtle: &str) -> Result<Option<i64>, Error> {
+    let graph = AssessmentGraph::new()?;
+    graph.load(turtle)?;
+    Ok(graph.get_modified_timestmap().ok())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Scores {
     dataset: Score,
     distributions: Vec<Score>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Score {
     id: String,
     dimensions: Vec<DimensionScore>,
     score: u64,
     max_score: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coverage: Option<Coverage>,
+    /// Score as a percentage of max score, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    percentage: Option<u64>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// How a score percentage is rounded to a whole number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    Floor,
+    /// Half way values are rounded up.
+    Round,
+}
+
+impl TryFrom<&str> for Rounding {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "floor" => Ok(Self::Floor),
+            "round" => Ok(Self::Round),
+            _ => Err(format!("invalid rounding '{value}', expected 'floor' or 'round'").into()),
+        }
+    }
+}
+
+/// Score as a whole percentage of max score, 0 if max score is 0.
+pub fn percentage(score: u64, max_score: u64, rounding: Rounding) -> u64 {
+    if max_score == 0 {
+        return 0;
+    }
+    match rounding {
+        Rounding::Floor => score * 100 / max_score,
+        Rounding::Round => (score * 200 + max_score) / (2 * max_score),
+    }
+}
+
+/// Number of scored metrics, out of all defined metrics.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Coverage {
+    scored_metrics: u64,
+    total_metrics: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DimensionScore {
     id: String,
     metrics: Vec<MetricScore>,
@@ -31,7 +130,7 @@ pub struct DimensionScore {
     max_score: u64,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MetricScore {
     id: String,
     score: u64,
@@ -39,68 +138,1141 @@ pub struct MetricScore {
     max_score: u64,
 }
 
-fn convert_score(score_definitions: &ScoreDefinitions, score: &score::Score) -> Score {
-    let dimensions = score_definitions
+/// Score clamped to max score, e.g. when score definitions lower a max score below a
+/// computed score. Clamping is logged, as it means definitions and measurements disagree.
+fn clamp_score(id: &str, score: u64, max_score: u64) -> u64 {
+    if score > max_score {
+        tracing::warn!(id, score, max_score, "score exceeds max score, clamping");
+    }
+    score.min(max_score)
+}
+
+/// Converts a single score to the api shape, e.g. that of one distribution.
+/// Coverage is left out, as it is only reported for datasets.
+/// Dimension and total scores are summed anew from clamped metric scores, so that they add up.
+pub fn convert_score(score_definitions: &ScoreDefinitions, score: &score::Score) -> Score {
+    let dimensions: Vec<DimensionScore> = score_definitions
         .dimensions
         .iter()
         .zip(score.dimensions.iter())
-        .map(|(score_dimension, dimension_score)| DimensionScore {
-            // .to_string() without .as_str() returns name wrapped in < >
-            id: dimension_score.id.as_str().to_string(),
-            metrics: score_dimension
+        .map(|(score_dimension, dimension_score)| {
+            let metrics: Vec<MetricScore> = score_dimension
                 .metrics
                 .iter()
                 .zip(dimension_score.metrics.iter())
                 .map(|(score_metric, metric_score)| MetricScore {
                     // .to_string() without .as_str() returns name wrapped in < >
                     id: metric_score.id.as_str().to_string(),
-                    score: metric_score.score.unwrap_or_default(),
+                    score: clamp_score(
+                        metric_score.id.as_str(),
+                        metric_score.score.unwrap_or_default(),
+                        score_metric.score,
+                    ),
                     is_scored: metric_score.score.is_some(),
                     max_score: score_metric.score,
                 })
-                .collect(),
-            score: dimension_score.score,
-            max_score: score_dimension.total_score,
+                .collect();
+            DimensionScore {
+                // .to_string() without .as_str() returns name wrapped in < >
+                id: dimension_score.id.as_str().to_string(),
+                score: clamp_score(
+                    dimension_score.id.as_str(),
+                    metrics.iter().map(|metric| metric.score).sum(),
+                    score_dimension.total_score,
+                ),
+                metrics,
+                max_score: score_dimension.total_score,
+            }
         })
         .collect();
 
+    let id = score.resource_iri();
     Score {
-        id: score.resource.as_str().to_string(),
+        score: clamp_score(
+            &id,
+            dimensions.iter().map(|dimension| dimension.score).sum(),
+            score_definitions.total_score,
+        ),
+        id,
         dimensions,
-        score: score.score,
         max_score: score_definitions.total_score,
+        coverage: None,
+        percentage: None,
     }
 }
 
+/// Converts the dataset score and distribution scores, told apart by their kind, to the api shape.
 pub fn convert_scores(
     score_definitions: &ScoreDefinitions,
-    dataset_score: &score::Score,
-    distribution_scores: &Vec<score::Score>,
-) -> Scores {
-    Scores {
-        dataset: convert_score(score_definitions, dataset_score),
+    scores: &[score::Score],
+) -> Result<Scores, Error> {
+    let (dataset_scores, distribution_scores): (Vec<_>, Vec<_>) = scores
+        .iter()
+        .partition(|score| score.kind == score::ScoreKind::Dataset);
+    let [dataset_score] = dataset_scores[..] else {
+        return Err(format!(
+            "expected exactly one dataset score, got {}",
+            dataset_scores.len()
+        )
+        .into());
+    };
+    let (scored_metrics, total_metrics) = score::coverage(dataset_score, score_definitions);
+    Ok(Scores {
+        dataset: Score {
+            coverage: Some(Coverage {
+                scored_metrics,
+                total_metrics,
+            }),
+            percentage: None,
+            ..convert_score(score_definitions, dataset_score)
+        },
         distributions: distribution_scores
             .into_iter()
             .map(|score| convert_score(score_definitions, score))
             .collect(),
+    })
+}
+
+impl Scores {
+    /// Scores with the percentage of dataset and distribution scores included.
+    pub fn with_percentage(self, rounding: Rounding) -> Scores {
+        let with_percentage = |score: Score| Score {
+            percentage: Some(percentage(score.score, score.max_score, rounding)),
+            ..score
+        };
+        Scores {
+            dataset: with_percentage(self.dataset),
+            distributions: self
+                .distributions
+                .into_iter()
+                .map(with_percentage)
+                .collect(),
+        }
+    }
+
+    /// Scores without unscored metrics, to reduce payload size.
+    /// Dimension max scores still include the left out metrics.
+    pub fn without_unscored(self) -> Scores {
+        let without_unscored = |score: Score| Score {
+            dimensions: score
+                .dimensions
+                .into_iter()
+                .map(|dimension| DimensionScore {
+                    metrics: dimension
+                        .metrics
+                        .into_iter()
+                        .filter(|metric| metric.is_scored)
+                        .collect(),
+                    ..dimension
+                })
+                .collect(),
+            ..score
+        };
+        Scores {
+            dataset: without_unscored(self.dataset),
+            distributions: self
+                .distributions
+                .into_iter()
+                .map(without_unscored)
+                .collect(),
+        }
+    }
+
+    /// Scores with only the dimensions changed since `previous`, for partial updates.
+    /// Distributions without changed dimensions are left out, while the dataset is always kept.
+    pub fn delta(&self, previous: &Scores) -> Scores {
+        let previous = std::iter::once(&previous.dataset)
+            .chain(previous.distributions.iter())
+            .map(|score| (score.id.as_str(), score))
+            .collect::<HashMap<&str, &Score>>();
+
+        Scores {
+            dataset: self
+                .dataset
+                .delta(previous.get(self.dataset.id.as_str()).copied()),
+            distributions: self
+                .distributions
+                .iter()
+                .map(|score| score.delta(previous.get(score.id.as_str()).copied()))
+                .filter(|score| !score.dimensions.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl Scores {
+    /// Max of each metric score, matching resources, dimensions and metrics by id.
+    fn merge(&self, other: &Scores) -> Scores {
+        let mut distributions = self.distributions.clone();
+        for score in other.distributions.iter() {
+            match distributions
+                .iter_mut()
+                .find(|existing| existing.id == score.id)
+            {
+                Some(existing) => *existing = existing.merge(score),
+                None => distributions.push(score.clone()),
+            }
+        }
+        Scores {
+            dataset: self.dataset.merge(&other.dataset),
+            distributions,
+        }
+    }
+
+    /// Scores of the assessments in `graph`, matched by resource, e.g. to insert merged scores.
+    fn node_scores(&self, graph: &AssessmentGraph) -> Result<Vec<score::Score>, Error> {
+        let nodes = std::iter::once(graph.dataset()?)
+            .chain(graph.distributions()?)
+            .map(|node| (score::resource_iri(&node.assessment, &node.resource), node))
+            .collect::<HashMap<_, _>>();
+        std::iter::once((score::ScoreKind::Dataset, &self.dataset))
+            .chain(
+                self.distributions
+                    .iter()
+                    .map(|score| (score::ScoreKind::Distribution, score)),
+            )
+            .map(|(kind, score)| {
+                let node = nodes
+                    .get(&score.id)
+                    .ok_or_else(|| format!("no assessment of resource '{}'", score.id))?;
+                Ok(score::Score {
+                    kind,
+                    assessment: node.assessment.clone(),
+                    resource: node.resource.clone(),
+                    dimensions: score
+                        .dimensions
+                        .iter()
+                        .map(DimensionScore::node_score)
+                        .collect::<Result<_, Error>>()?,
+                    score: score.score,
+                    best_distribution: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Score {
+    /// Max of each metric score, with dimension and total scores summed anew.
+    fn merge(&self, other: &Score) -> Score {
+        let mut dimensions = self.dimensions.clone();
+        for dimension in other.dimensions.iter() {
+            match dimensions
+                .iter_mut()
+                .find(|existing| existing.id == dimension.id)
+            {
+                Some(existing) => *existing = existing.merge(dimension),
+                None => dimensions.push(dimension.clone()),
+            }
+        }
+        let coverage = match (&self.coverage, &other.coverage) {
+            (None, None) => None,
+            (a, b) => Some(Coverage {
+                scored_metrics: dimensions
+                    .iter()
+                    .flat_map(|dimension| dimension.metrics.iter())
+                    .filter(|metric| metric.is_scored)
+                    .count() as u64,
+                total_metrics: a
+                    .iter()
+                    .chain(b.iter())
+                    .map(|coverage| coverage.total_metrics)
+                    .max()
+                    .unwrap_or_default(),
+            }),
+        };
+        Score {
+            id: self.id.clone(),
+            score: dimensions.iter().map(|dimension| dimension.score).sum(),
+            max_score: self.max_score.max(other.max_score),
+            dimensions,
+            coverage,
+            // Stale after merging, percentages are to be added to the merged scores.
+            percentage: None,
+        }
+    }
+
+    /// Score with dimensions equal to those of `previous` left out.
+    fn delta(&self, previous: Option<&Score>) -> Score {
+        Score {
+            dimensions: self
+                .dimensions
+                .iter()
+                .filter(|dimension| {
+                    previous.is_none_or(|previous| !previous.dimensions.contains(dimension))
+                })
+                .cloned()
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl DimensionScore {
+    fn merge(&self, other: &DimensionScore) -> DimensionScore {
+        let mut metrics = self.metrics.clone();
+        for metric in other.metrics.iter() {
+            match metrics.iter_mut().find(|existing| existing.id == metric.id) {
+                Some(existing) => {
+                    existing.score = existing.score.max(metric.score);
+                    existing.is_scored |= metric.is_scored;
+                }
+                None => metrics.push(metric.clone()),
+            }
+        }
+        DimensionScore {
+            id: self.id.clone(),
+            score: metrics.iter().map(|metric| metric.score).sum(),
+            max_score: self.max_score.max(other.max_score),
+            metrics,
+        }
+    }
+
+    fn node_score(&self) -> Result<score::DimensionScore, Error> {
+        Ok(score::DimensionScore {
+            id: NamedNode::new(&self.id)?,
+            metrics: self
+                .metrics
+                .iter()
+                .map(|metric| {
+                    Ok(score::MetricScore {
+                        id: NamedNode::new(&metric.id)?,
+                        score: metric.is_scored.then_some(metric.score),
+                        computed_on: None,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            score: self.score,
+        })
+    }
+}
+
+/// Change in score of a dimension or metric, for a dataset or distribution.
+#[derive(Debug, PartialEq)]
+pub struct ScoreDelta {
+    pub resource: String,
+    pub id: String,
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+    pub delta: i64,
+}
+
+/// Flattens scores into a map: (resource, dimension or metric) -> score.
+fn flatten_scores(scores: &Scores) -> BTreeMap<(String, String), u64> {
+    let mut values = BTreeMap::new();
+    for score in std::iter::once(&scores.dataset).chain(scores.distributions.iter()) {
+        for dimension in &score.dimensions {
+            values.insert((score.id.clone(), dimension.id.clone()), dimension.score);
+            for metric in &dimension.metrics {
+                values.insert((score.id.clone(), metric.id.clone()), metric.score);
+            }
+        }
+    }
+    values
+}
+
+/// Lists all dimension and metric scores that differ between two scorings.
+pub fn diff_scores(old: &Scores, new: &Scores) -> Vec<ScoreDelta> {
+    let old = flatten_scores(old);
+    let new = flatten_scores(new);
+
+    old.keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|key| {
+            let (old, new) = (old.get(key).cloned(), new.get(key).cloned());
+            if old == new {
+                return None;
+            }
+            Some(ScoreDelta {
+                resource: key.0.clone(),
+                id: key.1.clone(),
+                old,
+                new,
+                delta: new.unwrap_or_default() as i64 - old.unwrap_or_default() as i64,
+            })
+        })
+        .collect()
+}
+
+/// Percentile rank of each dataset total score among all given scores, in input order.
+/// Tied scores share the average rank.
+pub fn percentile_ranks(scores: &[Scores]) -> Vec<f64> {
+    let totals: Vec<u64> = scores.iter().map(|scores| scores.dataset.score).collect();
+    let n = totals.len() as f64;
+    totals
+        .iter()
+        .map(|total| {
+            let below = totals.iter().filter(|other| *other < total).count() as f64;
+            let equal = totals.iter().filter(|other| *other == total).count() as f64;
+            100.0 * (below + 0.5 * equal) / n
+        })
+        .collect()
+}
+
+/// Rollup of dataset scores within a catalog.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CatalogScore {
+    dataset_count: u64,
+    mean_score: f64,
+    median_score: f64,
+    dimensions: Vec<DimensionAverage>,
+}
+
+/// Mean score of a dimension across the datasets of a catalog.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DimensionAverage {
+    id: String,
+    mean_score: f64,
+}
+
+/// Aggregates dataset total and dimension scores of the datasets in a catalog.
+/// Dimensions are sorted by IRI, and averaged over the datasets that have them.
+pub fn catalog_score(scores: &[Scores]) -> CatalogScore {
+    let mut totals: Vec<u64> = scores.iter().map(|scores| scores.dataset.score).collect();
+    totals.sort();
+    let n = totals.len();
+    let mean_score = match n {
+        0 => 0.0,
+        _ => totals.iter().sum::<u64>() as f64 / n as f64,
+    };
+    let median_score = match n {
+        0 => 0.0,
+        _ => (totals[(n - 1) / 2] + totals[n / 2]) as f64 / 2.0,
+    };
+
+    let mut dimensions: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+    for dimension in scores
+        .iter()
+        .flat_map(|scores| scores.dataset.dimensions.iter())
+    {
+        dimensions
+            .entry(dimension.id.as_str())
+            .or_default()
+            .push(dimension.score);
+    }
+
+    CatalogScore {
+        dataset_count: n as u64,
+        mean_score,
+        median_score,
+        dimensions: dimensions
+            .into_iter()
+            .map(|(id, scores)| DimensionAverage {
+                id: id.to_string(),
+                mean_score: scores.iter().sum::<u64>() as f64 / scores.len() as f64,
+            })
+            .collect(),
+    }
+}
+
+/// Dataset dimensions, sorted by IRI for stable column order.
+fn sorted_dimensions(scores: &Scores) -> Vec<&DimensionScore> {
+    let mut dimensions: Vec<&DimensionScore> = scores.dataset.dimensions.iter().collect();
+    dimensions.sort_by(|a, b| a.id.cmp(&b.id));
+    dimensions
+}
+
+/// Last segment of an IRI, after '#' or '/'.
+fn iri_suffix(iri: &str) -> &str {
+    iri.rsplit(['#', '/']).next().unwrap_or(iri)
+}
+
+/// Quotes a CSV field if it contains separators or quotes.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
     }
 }
 
+/// CSV header matching `scores_to_csv_row`.
+pub fn scores_csv_header(scores: &Scores) -> String {
+    [
+        "id".to_string(),
+        "score".to_string(),
+        "max_score".to_string(),
+    ]
+    .into_iter()
+    .chain(sorted_dimensions(scores).into_iter().flat_map(|dimension| {
+        let name = iri_suffix(&dimension.id);
+        [format!("{name}_score"), format!("{name}_max_score")]
+    }))
+    .map(|column| csv_field(&column))
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+/// Flattens dataset total and dimension scores into a CSV row.
+pub fn scores_to_csv_row(scores: &Scores) -> String {
+    [
+        csv_field(&scores.dataset.id),
+        scores.dataset.score.to_string(),
+        scores.dataset.max_score.to_string(),
+    ]
+    .into_iter()
+    .chain(
+        sorted_dimensions(scores)
+            .into_iter()
+            .flat_map(|dimension| [dimension.score.to_string(), dimension.max_score.to_string()]),
+    )
+    .collect::<Vec<String>>()
+    .join(",")
+}
+
+/// Scoring methodology, i.e. the max score of each metric and dimension, e.g. for frontends.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Definitions {
+    dimensions: Vec<DimensionDefinition>,
+    max_score: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DimensionDefinition {
+    id: String,
+    metrics: Vec<MetricDefinition>,
+    max_score: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MetricDefinition {
+    id: String,
+    max_score: u64,
+}
+
+pub fn convert_definitions(score_definitions: &ScoreDefinitions) -> Definitions {
+    Definitions {
+        dimensions: score_definitions
+            .dimensions
+            .iter()
+            .map(|dimension| DimensionDefinition {
+                id: dimension.id.as_str().to_string(),
+                metrics: dimension
+                    .metrics
+                    .iter()
+                    .map(|metric| MetricDefinition {
+                        id: metric.id.as_str().to_string(),
+                        max_score: metric.score,
+                    })
+                    .collect(),
+                max_score: dimension.total_score,
+            })
+            .collect(),
+        max_score: score_definitions.total_score,
+    }
+}
+
+/// Bundled score definitions as JSON.
+pub fn definitions_json() -> Result<String, Error> {
+    Ok(serde_json::to_string(&convert_definitions(
+        default_score_definitions(),
+    ))?)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
-        assessment_graph::AssessmentGraph,
-        helpers::parse_graphs,
+        compare::canonical_triples,
+        measurement_value::MeasurementValue,
         score::calculate_score,
         score_graph::ScoreGraph,
-        test::{MEASUREMENT_GRAPH, METRIC_GRAPH, SCORE_GRAPH},
+        test::{node, MEASUREMENT_GRAPH, METRIC_GRAPH, SCORE_GRAPH},
+        testkit::CapturedEvents,
+        vocab::{dcat_mqa, dcat_terms},
     };
+    use oxigraph::model::NamedNodeRef;
+    use tracing_subscriber::{layer::SubscriberExt, registry};
 
     use super::*;
 
+    #[test]
+    fn definitions() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(convert_definitions(&score_definitions)).unwrap(),
+            serde_json::json!({
+                "dimensions": [
+                    {
+                        "id": "https://data.norge.no/vocabulary/dcatno-mqa#accessibility",
+                        "metrics": [
+                            {
+                                "id": "https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode",
+                                "max_score": 50,
+                            },
+                            {
+                                "id": "https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability",
+                                "max_score": 20,
+                            },
+                        ],
+                        "max_score": 70,
+                    },
+                    {
+                        "id": "https://data.norge.no/vocabulary/dcatno-mqa#interoperability",
+                        "metrics": [
+                            {
+                                "id": "https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability",
+                                "max_score": 20,
+                            },
+                        ],
+                        "max_score": 20,
+                    },
+                ],
+                "max_score": 90,
+            })
+        );
+    }
+
+    #[test]
+    fn bundled_definitions_json() {
+        let definitions: Definitions = serde_json::from_str(&definitions_json().unwrap()).unwrap();
+        let max_score = |name: &str| {
+            let id = format!("https://data.norge.no/vocabulary/dcatno-mqa#{name}");
+            definitions
+                .dimensions
+                .iter()
+                .flat_map(|dimension| dimension.metrics.iter())
+                .find(|metric| metric.id == id)
+                .map(|metric| metric.max_score)
+        };
+        assert_eq!(max_score("accessUrlStatusCode"), Some(50));
+        assert_eq!(max_score("downloadUrlAvailability"), Some(20));
+        assert_eq!(max_score("formatAvailability"), Some(20));
+        assert_eq!(
+            definitions.max_score,
+            default_score_definitions().total_score
+        );
+    }
+
+    fn sample_scores() -> Scores {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        convert_scores(
+            &score_definitions,
+            &[vec![dataset_score], distribution_scores].concat(),
+        )
+        .unwrap()
+    }
+
+    /// Update request of a graph scored the way events are, modified at `modified`.
+    fn update_request(graph: &str, modified: i64) -> UpdateRequest {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(graph).unwrap();
+        measurement_graph
+            .insert_modified_timestmap(modified)
+            .unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+        let scores = [vec![dataset_score], distribution_scores].concat();
+        measurement_graph.insert_scores(&scores).unwrap();
+        measurement_graph.insert_provenance(Utc::now()).unwrap();
+        UpdateRequest {
+            turtle_assessment: measurement_graph.to_turtle().unwrap(),
+            jsonld_assessment: String::new(),
+            scores: convert_scores(&score_definitions, &scores).unwrap(),
+            partial: false,
+        }
+    }
+
+    /// Update requests measuring disjoint and overlapping metrics of the same dataset,
+    /// the latter modified later.
+    fn overlapping_update_requests() -> (UpdateRequest, UpdateRequest) {
+        let dataset = r#"
+            <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
+        "#;
+        let a = update_request(
+            &format!(
+                r#"{dataset}
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#
+            ),
+            1647698566000,
+        );
+        let b = update_request(
+            &format!(
+                r#"{dataset}
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:b .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                _:b <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode> .
+                _:b <http://www.w3.org/ns/dqv#value> "200"^^<http://www.w3.org/2001/XMLSchema#integer> .
+                "#
+            ),
+            1647698567000,
+        );
+        (a, b)
+    }
+
+    #[test]
+    fn merge_update_requests() {
+        let (a, b) = overlapping_update_requests();
+        assert_eq!(a.scores.dataset.score, 20);
+        assert_eq!(b.scores.dataset.score, 50);
+
+        let merged = a.merge(b).unwrap();
+        // Shared dataset triples are deduplicated, measurement nodes are kept from both.
+        let measurements = AssessmentGraph::new().unwrap();
+        measurements.load(&merged.turtle_assessment).unwrap();
+        measurements.remove_scores().unwrap();
+        assert_eq!(
+            canonical_triples(&measurements.to_turtle().unwrap())
+                .unwrap()
+                .len(),
+            11
+        );
+        assert_eq!(merged.jsonld_assessment, "");
+        assert!(!merged.partial);
+
+        let accessibility = &merged.scores.dataset.dimensions[0];
+        assert_eq!(
+            accessibility
+                .metrics
+                .iter()
+                .map(|metric| (iri_suffix(&metric.id), metric.score))
+                .collect::<Vec<_>>(),
+            vec![("accessUrlStatusCode", 50), ("downloadUrlAvailability", 20)]
+        );
+        assert_eq!(accessibility.score, 70);
+        assert_eq!(merged.scores.dataset.score, 70);
+        assert_eq!(
+            merged.scores.dataset.coverage,
+            Some(Coverage {
+                scored_metrics: 2,
+                total_metrics: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn merged_graph_scored_once() {
+        let (a, b) = overlapping_update_requests();
+        let merged = a.merge(b).unwrap();
+
+        let graph = AssessmentGraph::new().unwrap();
+        graph.load(&merged.turtle_assessment).unwrap();
+        let measurements = graph.quality_measurements().unwrap();
+        let scoring = |metric: NamedNodeRef| {
+            measurements[&(node("https://dataset.assessment.foo"), metric.into_owned())].clone()
+        };
+        // Scored by the merged scores, not by those of both requests side by side.
+        assert_eq!(scoring(dcat_mqa::SCORING), vec![MeasurementValue::Int(70)]);
+        assert_eq!(
+            scoring(dcat_mqa::ACCESSIBILITY_SCORING),
+            vec![MeasurementValue::Int(70)]
+        );
+        assert_eq!(graph.get_modified_timestmap().unwrap(), 1647698567000);
+
+        let triples = canonical_triples(&merged.turtle_assessment).unwrap();
+        let count = |predicate: NamedNodeRef| {
+            triples
+                .iter()
+                .filter(|triple| triple.contains(&predicate.to_string()))
+                .count()
+        };
+        assert_eq!(count(dcat_mqa::SCORE), 2);
+        assert_eq!(count(dcat_terms::MODIFIED), 1);
+        assert_eq!(count(dcat_terms::CREATED), 1);
+    }
+
+    #[test]
+    fn percentage_rounding() {
+        for (score, max_score, floor, round) in [
+            (1, 8, 12, 13),
+            (1, 200, 0, 1),
+            (3, 8, 37, 38),
+            (7, 8, 87, 88),
+            (1, 3, 33, 33),
+            (2, 3, 66, 67),
+            (70, 90, 77, 78),
+            (90, 90, 100, 100),
+            (0, 0, 0, 0),
+        ] {
+            assert_eq!(
+                percentage(score, max_score, Rounding::Floor),
+                floor,
+                "{score}/{max_score}"
+            );
+            assert_eq!(
+                percentage(score, max_score, Rounding::Round),
+                round,
+                "{score}/{max_score}"
+            );
+        }
+    }
+
+    #[test]
+    fn scores_with_percentage() {
+        let scores = sample_scores();
+        assert!(!serde_json::to_string(&scores)
+            .unwrap()
+            .contains("percentage"));
+
+        let scores = scores.with_percentage(Rounding::Floor);
+        assert_eq!(scores.dataset.percentage, Some(77));
+        assert_eq!(
+            scores
+                .distributions
+                .iter()
+                .map(|score| score.percentage)
+                .collect::<Vec<_>>(),
+            vec![Some(55), Some(22)]
+        );
+        assert!(serde_json::to_string(&scores)
+            .unwrap()
+            .contains(r#""percentage":77"#));
+        assert_eq!(Rounding::try_from(" Round").unwrap(), Rounding::Round);
+        assert!(Rounding::try_from("ceil").is_err());
+    }
+
+    #[test]
+    fn scores_clamped_to_max() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (mut dataset_score, _) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+        // Max score of formatAvailability and interoperability is 20, and total max score is 90.
+        let interoperability = &mut dataset_score.dimensions[1];
+        let excess = 30 - interoperability.metrics[0].score.unwrap_or_default();
+        interoperability.metrics[0].score = Some(30);
+        interoperability.score += excess;
+        dataset_score.score += excess;
+        assert!(dataset_score.score > 90);
+
+        let events = CapturedEvents::default();
+        let scores = tracing::subscriber::with_default(registry().with(events.clone()), || {
+            convert_scores(&score_definitions, &[dataset_score]).unwrap()
+        });
+
+        let interoperability = &scores.dataset.dimensions[1];
+        assert_eq!(
+            (
+                interoperability.metrics[0].score,
+                interoperability.metrics[0].max_score
+            ),
+            (20, 20)
+        );
+        assert_eq!(
+            (interoperability.score, interoperability.max_score),
+            (20, 20)
+        );
+        // Summed from the clamped metric score, rather than clamped in turn.
+        assert_eq!((scores.dataset.score, scores.dataset.max_score), (90, 90));
+        // Scores within max score are kept as is.
+        assert_eq!(scores.dataset.dimensions[0].score, 70);
+
+        let events = events.0.lock().unwrap();
+        let clamped = events
+            .iter()
+            .filter(|event| {
+                event.get("message").map(String::as_str)
+                    == Some("score exceeds max score, clamping")
+            })
+            .map(|event| (iri_suffix(&event["id"]).to_string(), event["score"].clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            clamped,
+            vec![("formatAvailability".to_string(), "30".to_string())]
+        );
+    }
+
+    #[test]
+    fn dimension_sum_clamped_to_max() {
+        let mut score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, _) = calculate_score(&measurement_graph, &score_definitions).unwrap();
+        // Metrics within their max score, that sum to more than the max score of their dimension.
+        score_definitions.dimensions[0].total_score = 60;
+
+        let score = convert_score(&score_definitions, &dataset_score);
+        let accessibility = &score.dimensions[0];
+        assert_eq!(
+            accessibility
+                .metrics
+                .iter()
+                .map(|metric| metric.score)
+                .sum::<u64>(),
+            70
+        );
+        assert_eq!((accessibility.score, accessibility.max_score), (60, 60));
+        assert_eq!(
+            score.score,
+            score
+                .dimensions
+                .iter()
+                .map(|dimension| dimension.score)
+                .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn unscored_omitted() {
+        let all = serde_json::to_value(sample_scores()).unwrap();
+        let scored = serde_json::to_value(sample_scores().without_unscored()).unwrap();
+
+        let metric_ids = |score: &serde_json::Value| {
+            score["dimensions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .flat_map(|dimension| dimension["metrics"].as_array().unwrap())
+                .map(|metric| iri_suffix(metric["id"].as_str().unwrap()).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(metric_ids(&all["dataset"]), metric_ids(&scored["dataset"]));
+        assert_eq!(
+            metric_ids(&scored["distributions"][0]),
+            vec!["accessUrlStatusCode", "formatAvailability"]
+        );
+        assert_eq!(
+            metric_ids(&scored["distributions"][1]),
+            vec!["formatAvailability"]
+        );
+
+        // Only metrics are left out, dimension scores and max scores are kept.
+        let mut expected = all;
+        expected["distributions"][0]["dimensions"][0]["metrics"]
+            .as_array_mut()
+            .unwrap()
+            .remove(1);
+        expected["distributions"][1]["dimensions"][0]["metrics"] = serde_json::json!([]);
+        assert_eq!(expected, scored);
+    }
+
+    fn dataset_total(score: u64) -> Scores {
+        Scores {
+            dataset: Score {
+                id: "https://dataset.foo".to_string(),
+                dimensions: vec![],
+                score,
+                max_score: 100,
+                coverage: None,
+                percentage: None,
+            },
+            distributions: vec![],
+        }
+    }
+
+    #[test]
+    fn delta_unchanged() {
+        let scores = sample_scores();
+        let delta = scores.delta(&sample_scores());
+        assert_eq!(delta.dataset.dimensions, vec![]);
+        assert_eq!(delta.dataset.score, scores.dataset.score);
+        assert_eq!(delta.distributions, vec![]);
+    }
+
+    #[test]
+    fn delta_changed_dimension() {
+        let previous = sample_scores();
+        let mut scores = sample_scores();
+        let interoperability = &mut scores.dataset.dimensions[1];
+        interoperability.metrics[0].score = 20;
+        interoperability.score = 20;
+
+        let delta = scores.delta(&previous);
+        assert_eq!(
+            delta.dataset.dimensions,
+            vec![scores.dataset.dimensions[1].clone()]
+        );
+        assert_eq!(delta.distributions, vec![]);
+    }
+
+    #[test]
+    fn delta_new_dataset() {
+        let scores = sample_scores();
+        assert_eq!(scores.delta(&dataset_total(0)), scores);
+    }
+
+    #[test]
+    fn percentiles() {
+        let scores: Vec<Scores> = [20, 10, 40, 20].into_iter().map(dataset_total).collect();
+        assert_eq!(percentile_ranks(&scores), vec![50.0, 12.5, 87.5, 50.0]);
+        assert_eq!(percentile_ranks(&[dataset_total(70)]), vec![50.0]);
+        assert_eq!(percentile_ranks(&[]), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn catalog() {
+        let mut improved = sample_scores();
+        improved.dataset.dimensions[1].score = 20;
+        improved.dataset.score = 90;
+        let scores = vec![sample_scores(), improved, dataset_total(20)];
+
+        assert_eq!(
+            catalog_score(&scores),
+            CatalogScore {
+                dataset_count: 3,
+                mean_score: 60.0,
+                median_score: 70.0,
+                dimensions: vec![
+                    DimensionAverage {
+                        id: "https://data.norge.no/vocabulary/dcatno-mqa#accessibility".to_string(),
+                        mean_score: 70.0,
+                    },
+                    DimensionAverage {
+                        id: "https://data.norge.no/vocabulary/dcatno-mqa#interoperability"
+                            .to_string(),
+                        mean_score: 10.0,
+                    },
+                ],
+            }
+        );
+        assert_eq!(catalog_score(&scores[..2]).median_score, 80.0);
+        assert_eq!(catalog_score(&[]).dataset_count, 0);
+    }
+
+    #[test]
+    fn csv_row() {
+        let scores = sample_scores();
+        assert_eq!(
+            scores_csv_header(&scores),
+            "id,score,max_score,accessibility_score,accessibility_max_score,interoperability_score,interoperability_max_score"
+        );
+        assert_eq!(
+            scores_to_csv_row(&scores),
+            "https://dataset.foo,70,90,70,70,0,20"
+        );
+    }
+
+    #[test]
+    fn diff_unchanged() {
+        assert_eq!(diff_scores(&sample_scores(), &sample_scores()), vec![]);
+    }
+
+    #[test]
+    fn diff_improved_metric() {
+        let old = sample_scores();
+        let mut new = sample_scores();
+        let interoperability = &mut new.dataset.dimensions[1];
+        interoperability.metrics[0].score = 20;
+        interoperability.score = 20;
+
+        assert_eq!(
+            diff_scores(&old, &new),
+            vec![
+                ScoreDelta {
+                    resource: "https://dataset.foo".to_string(),
+                    id: "https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability"
+                        .to_string(),
+                    old: Some(0),
+                    new: Some(20),
+                    delta: 20,
+                },
+                ScoreDelta {
+                    resource: "https://dataset.foo".to_string(),
+                    id: "https://data.norge.no/vocabulary/dcatno-mqa#interoperability".to_string(),
+                    old: Some(0),
+                    new: Some(20),
+                    delta: 20,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_dropped_metric() {
+        let old = sample_scores();
+        let mut new = sample_scores();
+        new.dataset.dimensions[0].metrics.remove(1);
+
+        assert_eq!(
+            diff_scores(&old, &new),
+            vec![ScoreDelta {
+                resource: "https://dataset.foo".to_string(),
+                id: "https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability"
+                    .to_string(),
+                old: Some(20),
+                new: None,
+                delta: -20,
+            }]
+        );
+    }
+
+    #[test]
+    fn single_distribution_score() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        let score = convert_score(&score_definitions, &distribution_scores[0]);
+        assert_eq!(score.id, "https://distribution.a");
+        assert_eq!(score.score, 50);
+        assert_eq!(score.max_score, 90);
+        assert_eq!(score.coverage, None);
+        assert_eq!(score.dimensions[0].metrics[0].max_score, 50);
+        assert!(!score.dimensions[0].metrics[1].is_scored);
+        let scores = convert_scores(
+            &score_definitions,
+            &[vec![dataset_score], distribution_scores].concat(),
+        )
+        .unwrap();
+        assert_eq!(score, scores.distributions[0]);
+    }
+
+    #[test]
+    fn scores_split_by_kind() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        // Order doesn't matter, the dataset score is the one of kind dataset.
+        let scores = convert_scores(
+            &score_definitions,
+            &[distribution_scores.clone(), vec![dataset_score.clone()]].concat(),
+        )
+        .unwrap();
+        assert_eq!(scores.dataset.id, "https://dataset.foo");
+        assert!(scores.dataset.coverage.is_some());
+        assert_eq!(scores.distributions.len(), distribution_scores.len());
+
+        assert!(convert_scores(&score_definitions, &distribution_scores).is_err());
+        assert!(
+            convert_scores(&score_definitions, &[dataset_score.clone(), dataset_score]).is_err()
+        );
+    }
+
     #[test]
     fn score() {
-        let score_definitions = ScoreGraph(parse_graphs(vec![METRIC_GRAPH, SCORE_GRAPH]).unwrap())
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
             .scores()
             .unwrap();
 
@@ -109,7 +1281,11 @@ mod tests {
         let (dataset_score, distribution_scores) =
             calculate_score(&measurement_graph, &score_definitions).unwrap();
 
-        let scores = convert_scores(&score_definitions, &dataset_score, &distribution_scores);
+        let scores = convert_scores(
+            &score_definitions,
+            &[vec![dataset_score], distribution_scores].concat(),
+        )
+        .unwrap();
 
         assert_eq!(scores, Scores {
             dataset: Score {
@@ -150,18 +1326,23 @@ mod tests {
                 ],
                 score: 70,
                 max_score: 90,
+                coverage: Some(Coverage {
+                    scored_metrics: 3,
+                    total_metrics: 3,
+                }),
+                percentage: None,
             },
             distributions: vec![
                 Score {
-                    id: "https://distribution.b".to_string(),
+                    id: "https://distribution.a".to_string(),
                     dimensions: vec![
                         DimensionScore {
                             id: "https://data.norge.no/vocabulary/dcatno-mqa#accessibility".to_string(),
                             metrics: vec![
                                 MetricScore {
                                     id: "https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode".to_string(),
-                                    score: 0,
-                                    is_scored: false,
+                                    score: 50,
+                                    is_scored: true,
                                     max_score: 50,
                                 },
                                 MetricScore {
@@ -171,7 +1352,7 @@ mod tests {
                                     max_score: 20,
                                 },
                             ],
-                            score: 0,
+                            score: 50,
                             max_score: 70,
                         },
                         DimensionScore {
@@ -179,28 +1360,30 @@ mod tests {
                             metrics: vec![
                                 MetricScore {
                                     id: "https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability".to_string(),
-                                    score: 20,
+                                    score: 0,
                                     is_scored: true,
                                     max_score: 20,
                                 },
                             ],
-                            score: 20,
+                            score: 0,
                             max_score: 20,
                         },
                     ],
-                    score: 20,
+                    score: 50,
                     max_score: 90,
+                    coverage: None,
+                    percentage: None,
                 },
                 Score {
-                    id: "https://distribution.a".to_string(),
+                    id: "https://distribution.b".to_string(),
                     dimensions: vec![
                         DimensionScore {
                             id: "https://data.norge.no/vocabulary/dcatno-mqa#accessibility".to_string(),
                             metrics: vec![
                                 MetricScore {
                                     id: "https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode".to_string(),
-                                    score: 50,
-                                    is_scored: true,
+                                    score: 0,
+                                    is_scored: false,
                                     max_score: 50,
                                 },
                                 MetricScore {
@@ -210,7 +1393,7 @@ mod tests {
                                     max_score: 20,
                                 },
                             ],
-                            score: 50,
+                            score: 0,
                             max_score: 70,
                         },
                         DimensionScore {
@@ -218,17 +1401,19 @@ mod tests {
                             metrics: vec![
                                 MetricScore {
                                     id: "https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability".to_string(),
-                                    score: 0,
+                                    score: 20,
                                     is_scored: true,
                                     max_score: 20,
                                 },
                             ],
-                            score: 0,
+                            score: 20,
                             max_score: 20,
                         },
                     ],
-                    score: 50,
+                    score: 20,
                     max_score: 90,
+                    coverage: None,
+                    percentage: None,
                 },
             ],
         });
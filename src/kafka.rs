@@ -1,47 +1,86 @@
 use std::{
-    env,
+    io::BufReader,
+    iter,
+    sync::Arc,
     time::{Duration, Instant},
 };
 
-use apache_avro::schema::Name;
+use apache_avro::{schema::Name, types::Value};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::Utc;
+use futures::StreamExt;
 use lazy_static::lazy_static;
+use oxigraph::model::NamedOrBlankNode;
 use rdkafka::{
-    consumer::{Consumer, StreamConsumer},
+    consumer::{BaseConsumer, CommitMode, Consumer, StreamConsumer},
     error::KafkaError,
     message::BorrowedMessage,
-    ClientConfig, Message,
+    producer::{FutureProducer, FutureRecord},
+    ClientConfig, Message, Offset, TopicPartitionList,
 };
 use reqwest::StatusCode;
 use schema_registry_converter::{
-    async_impl::{avro::AvroDecoder, schema_registry::SrSettings},
+    async_impl::{
+        avro::{AvroDecoder, AvroEncoder},
+        schema_registry::SrSettings,
+    },
     avro_common::DecodeResult,
+    error::SRCError,
+    schema_registry_common::SubjectNameStrategy,
 };
+use tokio_util::io::{StreamReader, SyncIoBridge};
 use tracing::{Instrument, Level};
 use uuid::Uuid;
 
 use crate::{
     assessment_graph::AssessmentGraph,
+    config::{Config, OffsetCommitMode},
     error::Error,
     json_conversion::{convert_scores, UpdateRequest},
-    metrics::{PROCESSED_MESSAGES, PROCESSING_TIME},
-    schemas::{InputEvent, MqaEvent, MqaEventType},
-    score::calculate_score,
-    score_graph::{ScoreDefinitions, ScoreGraph},
+    metrics::{CONSUMER_LAG, HTTP_REQUEST_DURATION, PROCESSED_MESSAGES, PROCESSING_TIME},
+    schemas::{InputEvent, LegacyDatasetEvent, MqaEvent, MqaEventType, ScoreEvent},
+    score::{calculate_score_with, coverage, Score},
+    score_graph::{ScoreDefinitions, SharedScoreDefinitions},
 };
 
 lazy_static! {
-    pub static ref BROKERS: String = env::var("BROKERS").unwrap_or("localhost:9092".to_string());
-    pub static ref SCHEMA_REGISTRY: String =
-        env::var("SCHEMA_REGISTRY").unwrap_or("http://localhost:8081".to_string());
-    pub static ref INPUT_TOPIC: String =
-        env::var("INPUT_TOPIC").unwrap_or("mqa-events".to_string());
-    pub static ref SCORING_API_URL: String =
-        env::var("SCORING_API_URL").unwrap_or("http://localhost:8082".to_string());
-    pub static ref SCORING_API_KEY: String = env::var("API_KEY").unwrap_or_default();
+    pub static ref CONFIG: Config = Config::from_env().unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "invalid configuration");
+        std::process::exit(1);
+    });
 }
 
-pub fn create_sr_settings() -> Result<SrSettings, Error> {
-    let mut schema_registry_urls = SCHEMA_REGISTRY.split(",");
+/// Converts a decoded avro value into an `MqaEvent`.
+type EventAdapter = fn(&Value) -> Result<MqaEvent, Error>;
+
+/// Adapter of events with full avro name `namespace.name`, if accepted as mqa events.
+fn event_adapter(
+    legacy_events: &[(String, String)],
+    namespace: &str,
+    name: &str,
+) -> Option<EventAdapter> {
+    if (namespace, name) == ("no.fdk.mqa", "MQAEvent") {
+        return Some(|value| Ok(apache_avro::from_value::<MqaEvent>(value)?));
+    }
+    legacy_events
+        .iter()
+        .any(|(legacy_namespace, legacy_name)| {
+            (legacy_namespace.as_str(), legacy_name.as_str()) == (namespace, name)
+        })
+        .then_some(|value| Ok(apache_avro::from_value::<LegacyDatasetEvent>(value)?.into()))
+}
+
+/// Kafka commit mode of an offset commit mode, if offsets are to be committed explicitly.
+fn kafka_commit_mode(mode: OffsetCommitMode) -> Option<CommitMode> {
+    match mode {
+        OffsetCommitMode::Store => None,
+        OffsetCommitMode::Sync => Some(CommitMode::Sync),
+        OffsetCommitMode::Async => Some(CommitMode::Async),
+    }
+}
+
+pub fn create_sr_settings(config: &Config) -> Result<SrSettings, Error> {
+    let mut schema_registry_urls = config.schema_registry.split(",");
 
     let mut sr_settings_builder =
         SrSettings::new_builder(schema_registry_urls.next().unwrap_or_default().to_string());
@@ -55,10 +94,11 @@ pub fn create_sr_settings() -> Result<SrSettings, Error> {
     Ok(sr_settings)
 }
 
-pub fn create_consumer() -> Result<StreamConsumer, KafkaError> {
-    let consumer: StreamConsumer = ClientConfig::new()
-        .set("group.id", "fdk-mqa-scoring-service")
-        .set("bootstrap.servers", BROKERS.clone())
+fn consumer_config(config: &Config) -> ClientConfig {
+    let mut client_config = ClientConfig::new();
+    client_config
+        .set("group.id", config.consumer_group_id.clone())
+        .set("bootstrap.servers", config.brokers.clone())
         .set("enable.partition.eof", "false")
         .set("session.timeout.ms", "6000")
         .set("enable.auto.commit", "true")
@@ -66,81 +106,229 @@ pub fn create_consumer() -> Result<StreamConsumer, KafkaError> {
         .set("auto.offset.reset", "beginning")
         .set("api.version.request", "false")
         .set("security.protocol", "plaintext")
-        .set("max.partition.fetch.bytes", "2097152")
-        .create()?;
-    consumer.subscribe(&[&INPUT_TOPIC])?;
+        .set("max.partition.fetch.bytes", "2097152");
+    client_config
+}
+
+pub fn create_consumer(config: &Config) -> Result<StreamConsumer, KafkaError> {
+    let consumer: StreamConsumer = consumer_config(config).create()?;
+    let topics = config
+        .input_topics
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    consumer.subscribe(&topics)?;
     Ok(consumer)
 }
 
-pub async fn run_async_processor(worker_id: usize, sr_settings: SrSettings) -> Result<(), Error> {
+/// Periodically updates the consumer lag of each input topic partition.
+pub async fn run_lag_reporter(config: &'static Config) -> Result<(), Error> {
+    // Not subscribed, only used to query committed offsets of the consumer group.
+    let consumer: Arc<BaseConsumer> = Arc::new(consumer_config(config).create()?);
+    let mut interval = tokio::time::interval(Duration::from_secs(30));
+    loop {
+        interval.tick().await;
+        let consumer = consumer.clone();
+        let topics = &config.input_topics;
+        let result = tokio::task::spawn_blocking(move || report_consumer_lag(&consumer, topics))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string().into()));
+        if let Err(e) = result {
+            tracing::warn!(error = e.to_string(), "unable to report consumer lag");
+        }
+    }
+}
+
+fn report_consumer_lag(consumer: &BaseConsumer, input_topics: &[String]) -> Result<(), Error> {
+    let timeout = Duration::from_secs(10);
+    let mut partitions = TopicPartitionList::new();
+    for input_topic in input_topics {
+        let metadata = consumer.fetch_metadata(Some(input_topic), timeout)?;
+        for topic in metadata.topics() {
+            for partition in topic.partitions() {
+                partitions.add_partition(topic.name(), partition.id());
+            }
+        }
+    }
+
+    let committed = consumer.committed_offsets(partitions, timeout)?;
+    for element in committed.elements() {
+        let (low, high) =
+            consumer.fetch_watermarks(element.topic(), element.partition(), timeout)?;
+        let committed = match element.offset() {
+            Offset::Offset(offset) => Some(offset),
+            _ => None,
+        };
+        CONSUMER_LAG
+            .with_label_values(&[element.topic(), &element.partition().to_string()])
+            .set(consumer_lag(committed, low, high));
+    }
+    Ok(())
+}
+
+/// Number of messages between committed offset and high watermark. When nothing is
+/// committed, the consumer starts from the low watermark.
+fn consumer_lag(committed: Option<i64>, low: i64, high: i64) -> i64 {
+    high - committed.unwrap_or(low).clamp(low, high)
+}
+
+pub fn create_http_client(config: &Config) -> Result<reqwest::Client, Error> {
+    build_http_client(config.request_timeout)
+}
+
+fn build_http_client(timeout: Duration) -> Result<reqwest::Client, Error> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(Duration::from_secs(10))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .pool_max_idle_per_host(4)
+        .gzip(true)
+        .build()?;
+    Ok(client)
+}
+
+/// Producer of score events to the output topic, used when scores are sunk to kafka.
+pub struct ScoreProducer {
+    producer: FutureProducer,
+    encoder: AvroEncoder<'static>,
+    topic: String,
+}
+
+impl ScoreProducer {
+    pub async fn produce(&self, event: &ScoreEvent) -> Result<(), Error> {
+        let encoded = self
+            .encoder
+            .encode_struct(
+                event,
+                &SubjectNameStrategy::RecordNameStrategy("no.fdk.mqa.MQAScoreEvent".to_string()),
+            )
+            .await?;
+        let record = FutureRecord::to(&self.topic)
+            .key(&event.fdk_id)
+            .payload(&encoded);
+        self.producer
+            .send(record, Duration::from_secs(10))
+            .await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
+
+/// Creates producer of score events, if scores are sunk to kafka.
+pub fn create_score_producer(
+    config: &Config,
+    sr_settings: SrSettings,
+) -> Result<Option<ScoreProducer>, Error> {
+    let topic = match &config.output_topic {
+        Some(topic) if config.sink.produces_to_kafka() => topic.clone(),
+        _ => return Ok(None),
+    };
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", config.brokers.clone())
+        .create()?;
+    Ok(Some(ScoreProducer {
+        producer,
+        encoder: AvroEncoder::new(sr_settings),
+        topic,
+    }))
+}
+
+/// Consumes and handles messages until failing.
+/// Requests to the scoring api are sent with `http_client` if given, e.g. one with custom
+/// headers or TLS, or with a client created from `config` otherwise.
+pub async fn run_async_processor(
+    worker_id: usize,
+    config: &'static Config,
+    sr_settings: SrSettings,
+    shared_score_definitions: SharedScoreDefinitions,
+    http_client: Option<reqwest::Client>,
+) -> Result<(), Error> {
     tracing::info!(worker_id, "starting worker");
 
-    let consumer: StreamConsumer = create_consumer()?;
+    let consumer: StreamConsumer = create_consumer(config)?;
+    let score_producer = create_score_producer(config, sr_settings.clone())?;
     let mut decoder = AvroDecoder::new(sr_settings);
-    let score_definitions = ScoreGraph::new()?.scores()?;
     let assessment_graph = AssessmentGraph::new()?;
-    let http_client = reqwest::Client::new();
+    let http_client = match http_client {
+        Some(http_client) => http_client,
+        None => create_http_client(config)?,
+    };
 
     tracing::info!(worker_id, "listening for messages");
     loop {
         let message = consumer.recv().await?;
+        let score_definitions = shared_score_definitions.load_full();
         let span = tracing::span!(
             Level::INFO,
             "message",
-            // topic = message.topic(),
+            topic = message.topic(),
             partition = message.partition(),
             offset = message.offset(),
             timestamp = message.timestamp().to_millis(),
         );
 
         receive_message(
+            config,
             &consumer,
             &mut decoder,
             &score_definitions,
             &assessment_graph,
             &http_client,
+            score_producer.as_ref(),
             &message,
         )
         .instrument(span)
-        .await;
+        .await?;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn receive_message(
+    config: &Config,
     consumer: &StreamConsumer,
     decoder: &mut AvroDecoder<'_>,
     score_definitions: &ScoreDefinitions,
     assessment_graph: &AssessmentGraph,
     http_client: &reqwest::Client,
+    score_producer: Option<&ScoreProducer>,
     message: &BorrowedMessage<'_>,
-) {
+) -> Result<(), Error> {
     let start_time = Instant::now();
     let mut attempts = 0;
-    let mut result: Result<(), Error> = Err("handle_message not attempted".into());
-
-    for _ in 0..5 {
+    let result = loop {
         attempts += 1;
-        result = handle_message(
+        let result = handle_message(
+            config,
             decoder,
             score_definitions,
             assessment_graph,
             http_client,
+            score_producer,
             message,
         )
         .await;
 
-        if let Ok(_) = result {
-            break;
+        if attempts >= 5 || !should_retry(&result) {
+            break result;
         }
         tokio::time::sleep(Duration::from_millis(3000)).await;
-    }
+    };
     let elapsed_millis = start_time.elapsed().as_millis();
 
-    match result {
+    match &result {
         Ok(_) => {
             tracing::info!(elapsed_millis, attempts, "message handled successfully");
             PROCESSED_MESSAGES.with_label_values(&["success"]).inc();
         }
+        Err(e) if e.is_permanent() => {
+            tracing::error!(
+                elapsed_millis,
+                attempts,
+                error = e.to_string(),
+                "permanently failed while handling message, dead-lettering it"
+            );
+            PROCESSED_MESSAGES.with_label_values(&["dead_letter"]).inc();
+        }
         Err(e) => {
             tracing::error!(
                 elapsed_millis,
@@ -152,44 +340,127 @@ async fn receive_message(
         }
     };
     PROCESSING_TIME.observe(elapsed_millis as f64 / 1000.0);
-    if let Err(e) = consumer.store_offset_from_message(&message) {
+
+    if !should_store_offset(&result, config.halt_on_error) {
+        tracing::error!("halting on failed message, offset not stored");
+        return result;
+    }
+    if result.is_err() {
+        tracing::warn!("storing offset of failed message, skipping it");
+    }
+    let commit_result = match kafka_commit_mode(config.commit_mode) {
+        None => consumer.store_offset_from_message(&message),
+        Some(mode) => consumer.commit_message(message, mode),
+    };
+    if let Err(e) = commit_result {
         tracing::warn!(error = e.to_string(), "failed to store offset");
     };
+    Ok(())
+}
+
+/// Whether a handled message should be attempted again, i.e. it failed with a transient error.
+fn should_retry(result: &Result<(), Error>) -> bool {
+    match result {
+        Ok(_) => false,
+        Err(e) => !e.is_permanent(),
+    }
+}
+
+/// Whether to store the offset of a handled message, moving past it.
+/// Failed messages are skipped unless halting on error.
+fn should_store_offset(result: &Result<(), Error>, halt_on_error: bool) -> bool {
+    result.is_ok() || !halt_on_error
 }
 
 pub async fn handle_message(
+    config: &Config,
     decoder: &mut AvroDecoder<'_>,
     score_definitions: &ScoreDefinitions,
     assessment_graph: &AssessmentGraph,
     http_client: &reqwest::Client,
+    score_producer: Option<&ScoreProducer>,
     message: &BorrowedMessage<'_>,
 ) -> Result<(), Error> {
-    match decode_message(decoder, message).await? {
+    match decode_message(
+        decoder,
+        message,
+        &config.legacy_events,
+        config.log_raw_payload,
+    )
+    .await?
+    {
         InputEvent::MqaEvent(event) => {
             let span = tracing::span!(
                 Level::INFO,
                 "event",
                 fdk_id = event.fdk_id.as_str(),
                 event_type = format!("{:?}", event.event_type).as_str(),
+                distribution_count = tracing::field::Empty,
+                best_distribution = tracing::field::Empty,
+                dimension_scores = tracing::field::Empty,
             );
 
-            handle_mqa_event(score_definitions, assessment_graph, http_client, event)
-                .instrument(span)
-                .await
-                .map_err(|e| e.to_string())?;
+            handle_mqa_event(
+                config,
+                score_definitions,
+                assessment_graph,
+                http_client,
+                score_producer,
+                event,
+            )
+            .instrument(span)
+            .await?;
         }
         InputEvent::Unknown { namespace, name } => {
             tracing::warn!(namespace, name, "skipping unknown event");
         }
+        InputEvent::Empty => {
+            tracing::warn!("skipping message without payload");
+        }
     }
     Ok(())
 }
 
-async fn decode_message(
+/// Decodes message, logging its topic, partition and offset on failure, e.g. a schema mismatch.
+/// The undecodable payload is logged as base64 at debug level if `log_raw_payload` is set.
+async fn decode_message<M: Message>(
     decoder: &mut AvroDecoder<'_>,
-    message: &BorrowedMessage<'_>,
+    message: &M,
+    legacy_events: &[(String, String)],
+    log_raw_payload: bool,
+) -> Result<InputEvent, Error> {
+    let result = decode_payload(decoder, message.payload(), legacy_events).await;
+    if let Err(e) = &result {
+        tracing::warn!(
+            topic = message.topic(),
+            partition = message.partition(),
+            offset = message.offset(),
+            error = e.to_string(),
+            "unable to decode message"
+        );
+        if log_raw_payload {
+            tracing::debug!(
+                payload = BASE64_STANDARD.encode(message.payload().unwrap_or_default()),
+                "raw payload of undecodable message"
+            );
+        }
+    }
+    result
+}
+
+async fn decode_payload(
+    decoder: &mut AvroDecoder<'_>,
+    payload: Option<&[u8]>,
+    legacy_events: &[(String, String)],
 ) -> Result<InputEvent, Error> {
-    match decoder.decode(message.payload()).await? {
+    if payload.is_none_or(<[u8]>::is_empty) {
+        return Ok(InputEvent::Empty);
+    }
+    match decoder
+        .decode(payload)
+        .await
+        .map_err(|e| classify_decode_error(decoder, e))?
+    {
         DecodeResult {
             name:
                 Some(Name {
@@ -198,102 +469,449 @@ async fn decode_message(
                     ..
                 }),
             value,
-        } => {
-            let event = match (namespace.as_str(), name.as_str()) {
-                ("no.fdk.mqa", "MQAEvent") => {
-                    InputEvent::MqaEvent(apache_avro::from_value::<MqaEvent>(&value)?)
-                }
-                _ => InputEvent::Unknown { namespace, name },
-            };
-            Ok(event)
-        }
+        } => input_event(legacy_events, namespace, name, &value),
         _ => Err("unable to identify event without namespace and name".into()),
     }
 }
 
+/// Classifies decode failures by whether the payload itself can not be decoded.
+/// Payloads with invalid bytes, or that do not match their schema, are permanent failures.
+/// Schema lookups failing otherwise are transient, also those the registry client deems not
+/// retriable, e.g. unparsable responses of a registry that is restarting.
+fn classify_decode_error(decoder: &AvroDecoder<'_>, e: SRCError) -> Error {
+    if e.error.starts_with("Invalid bytes") || e.error == "Could not transform bytes using schema" {
+        Error::Permanent(format!("unable to decode message: {e}"))
+    } else {
+        // Failed schema lookups are cached, and retries would fail without reaching the registry.
+        decoder.remove_errors_from_cache();
+        Error::SRCError(e)
+    }
+}
+
+/// Converts a decoded avro value into an input event, using the adapter of its name.
+fn input_event(
+    legacy_events: &[(String, String)],
+    namespace: String,
+    name: String,
+    value: &Value,
+) -> Result<InputEvent, Error> {
+    match event_adapter(legacy_events, &namespace, &name) {
+        Some(adapter) => Ok(InputEvent::MqaEvent(adapter(value)?)),
+        None => Ok(InputEvent::Unknown { namespace, name }),
+    }
+}
+
 async fn handle_mqa_event(
+    config: &Config,
     score_definitions: &ScoreDefinitions,
     assessment_graph: &AssessmentGraph,
     http_client: &reqwest::Client,
+    score_producer: Option<&ScoreProducer>,
     event: MqaEvent,
 ) -> Result<(), Error> {
     match event.event_type {
         MqaEventType::PropertiesChecked
         | MqaEventType::UrlsChecked
         | MqaEventType::DcatComplienceChecked => {
-            assessment_graph.clear()?;
-            let fdk_id = Uuid::parse_str(event.fdk_id.as_str())
-                .map_err(|e| format!("unable to parse FDK ID: {e}"))?;
-
-            if let Some(graph) = get_graph(&http_client, &fdk_id).await? {
-                assessment_graph.load(graph)?;
-
-                let current_timestamp = assessment_graph.get_modified_timestmap()?;
-                if current_timestamp < event.timestamp {
-                    tracing::debug!(
-                        existing_timestamp = current_timestamp,
-                        event_timestamp = event.timestamp,
-                        "overriding existing assessment"
-                    );
-                    assessment_graph.clear()?;
-                } else if current_timestamp > event.timestamp {
-                    tracing::debug!(
-                        existing_timestamp = current_timestamp,
-                        event_timestamp = event.timestamp,
-                        "skipping outdated assessment event"
-                    );
-                    return Ok(());
-                } else {
-                    tracing::debug!(
-                        existing_timestamp = current_timestamp,
-                        event_timestamp = event.timestamp,
-                        "merging with existing assessment"
-                    );
-                }
-            } else {
-                tracing::debug!("saving new assessment");
+            if !config.process_event_types.contains(&event.event_type) {
+                tracing::info!("skipping event type not in PROCESS_EVENT_TYPES");
+                return Ok(());
             }
 
-            assessment_graph.load(event.graph)?;
-            assessment_graph.insert_modified_timestmap(event.timestamp)?;
+            validate_timestamp(
+                event.timestamp,
+                Utc::now().timestamp_millis(),
+                config.max_clock_skew_secs,
+            )?;
+
+            let result = score_event(
+                config,
+                score_definitions,
+                assessment_graph,
+                http_client,
+                score_producer,
+                event,
+            )
+            .await;
+            // Release the graph's memory now rather than when the next message is handled,
+            // bounding peak memory of long-running workers handling very large graphs.
+            result.and(assessment_graph.clear())
+        }
+        MqaEventType::Unknown => unknown_event_type(config.strict_event_types),
+    }
+}
+
+/// Scores event graph, merged with or overriding the prior assessment, and sinks the scores.
+async fn score_event(
+    config: &Config,
+    score_definitions: &ScoreDefinitions,
+    assessment_graph: &AssessmentGraph,
+    http_client: &reqwest::Client,
+    score_producer: Option<&ScoreProducer>,
+    event: MqaEvent,
+) -> Result<(), Error> {
+    // The graph is reused across messages and retry attempts, start from scratch.
+    assessment_graph.clear()?;
+    let fdk_id = Uuid::parse_str(event.fdk_id.as_str())
+        .map_err(|e| Error::Permanent(format!("unable to parse FDK ID '{}': {e}", event.fdk_id)))?;
+
+    // Without posting to the api, the assessment stored there is not kept up to date,
+    // and every event is scored by itself, as a complete assessment.
+    let has_prior_graph = config.sink.posts_to_api()
+        && get_graph(config, http_client, &fdk_id, assessment_graph).await?;
+    let mut prior_measurements_hash = None;
+    if has_prior_graph {
+        let current_timestamp = assessment_graph.get_modified_timestmap()?;
+        if current_timestamp < event.timestamp {
+            tracing::debug!(
+                existing_timestamp = current_timestamp,
+                event_timestamp = event.timestamp,
+                "overriding existing assessment"
+            );
+            prior_measurements_hash = Some(assessment_graph.measurements_hash()?);
+            assessment_graph.clear()?;
+        } else if current_timestamp > event.timestamp {
+            tracing::debug!(
+                existing_timestamp = current_timestamp,
+                event_timestamp = event.timestamp,
+                "skipping outdated assessment event"
+            );
+            return Ok(());
+        } else {
+            tracing::debug!(
+                existing_timestamp = current_timestamp,
+                event_timestamp = event.timestamp,
+                "merging with existing assessment"
+            );
+        }
+    } else {
+        tracing::debug!("saving new assessment");
+    }
+
+    if config.validate_iris {
+        assessment_graph.load_validated(event.graph)?;
+    } else {
+        assessment_graph.load(event.graph)?;
+    }
+    // A re-emitted graph scores the same as the stored assessment, which is kept as is.
+    if prior_measurements_hash.is_some()
+        && prior_measurements_hash == Some(assessment_graph.measurements_hash()?)
+    {
+        tracing::debug!("measurements unchanged, not reposting scores");
+        return Ok(());
+    }
+    if !has_prior_graph {
+        if let Err(e) = assessment_graph.dataset() {
+            return Err(Error::Permanent(format!(
+                "no prior assessment exists, and event graph is incomplete: {e}"
+            )));
+        }
+    }
+    if let Ok(dataset) = assessment_graph.dataset() {
+        check_dataset_id(&dataset.resource, &fdk_id, config.strict_dataset_id)?;
+    }
+    if let Ok(orphans) = assessment_graph.orphan_measurements() {
+        for orphan in orphans {
+            tracing::warn!(
+                assessment = orphan.assessment.as_str(),
+                metric = orphan.metric.as_str(),
+                computed_on = orphan.computed_on.map(|node| node.to_string()),
+                "measurement of unknown assessment is not scored"
+            );
+        }
+    }
+    if let Ok(misplaced) = assessment_graph.misplaced_measurements() {
+        for measurement in misplaced {
+            tracing::warn!(
+                assessment = measurement.assessment.as_str(),
+                metric = measurement.metric.as_str(),
+                computed_on = measurement.computed_on.map(|node| node.to_string()),
+                "measurement computed on another resource than that of its assessment"
+            );
+        }
+    }
+    assessment_graph.insert_modified_timestmap(event.timestamp)?;
+
+    let (dataset_score, distribution_scores) =
+        calculate_score_with(assessment_graph, score_definitions, config.aggregation)?;
+    let (scored_metrics, _) = coverage(&dataset_score, score_definitions);
+    if scored_metrics == 0 && has_prior_graph {
+        tracing::warn!("assessment has no scored measurements, keeping existing scores");
+        return Ok(());
+    }
+    log_score_breakdown(score_definitions, &dataset_score);
 
-            let (dataset_score, distribution_scores) =
-                calculate_score(&assessment_graph, &score_definitions)?;
-            let scores = convert_scores(&score_definitions, &dataset_score, &distribution_scores);
+    let span = tracing::Span::current();
+    span.record(
+        "dimension_scores",
+        tracing::field::display(DimensionScores(&dataset_score)),
+    );
+    span.record("distribution_count", distribution_scores.len());
+    if let Some(best) = distribution_scores
+        .iter()
+        .find(|score| Some(&score.assessment) == dataset_score.best_distribution.as_ref())
+    {
+        span.record("best_distribution", best.resource_iri().as_str());
+    }
 
-            assessment_graph.insert_scores(&vec![dataset_score])?;
-            assessment_graph.insert_scores(&distribution_scores)?;
+    let node_scores: Vec<Score> = iter::once(dataset_score)
+        .chain(distribution_scores)
+        .collect();
+    // The graph keeps metrics without measurements unscored, the policy only applies to the api.
+    let mut scores = convert_scores(
+        &score_definitions,
+        &node_scores
+            .iter()
+            .map(|score| {
+                score
+                    .clone()
+                    .with_missing_measurements(config.missing_measurements)
+            })
+            .collect::<Vec<_>>(),
+    )?;
+    if let Some(rounding) = config.percentage_rounding {
+        scores = scores.with_percentage(rounding);
+    }
+    if config.omit_unscored {
+        scores = scores.without_unscored();
+    }
 
-            tracing::debug!("posting assessment to api");
-            let turtle_assessment = assessment_graph.to_turtle()?;
-            let jsonld_assessment = assessment_graph.turtle_to_jsonld(&turtle_assessment)?;
-            post_scores(
-                &http_client,
-                &fdk_id,
-                UpdateRequest {
-                    scores,
-                    turtle_assessment,
-                    jsonld_assessment,
-                },
-            )
-            .await
+    assessment_graph.insert_scores(&node_scores)?;
+    assessment_graph.insert_provenance(Utc::now())?;
+
+    tracing::debug!("posting assessment to api");
+    let turtle_assessment = if config.prefixed_turtle {
+        assessment_graph.to_prefixed_turtle()?
+    } else {
+        assessment_graph.to_turtle()?
+    };
+    let jsonld_assessment =
+        jsonld_assessment(assessment_graph, &turtle_assessment, config.emit_jsonld)?;
+    let update = limit_payload(
+        UpdateRequest {
+            scores,
+            turtle_assessment,
+            jsonld_assessment,
+            partial: false,
+        },
+        config.max_payload_bytes,
+    )?;
+    let score_event = ScoreEvent {
+        fdk_id: fdk_id.to_string(),
+        scores: serde_json::to_string(&update.scores)?,
+        timestamp: event.timestamp,
+    };
+    // Posted first, so that a failed post retried with both sinks doesn't produce the scores
+    // of the event more than once.
+    if config.sink.posts_to_api() {
+        post_scores(config, http_client, &fdk_id, update).await?;
+    }
+    if let Some(score_producer) = score_producer {
+        tracing::debug!("producing scores to output topic");
+        score_producer.produce(&score_event).await?;
+    }
+    Ok(())
+}
+
+/// Checks that the dataset IRI contains the FDK ID of the event, guarding against misrouted
+/// events overwriting scores of another dataset. Mismatches are only logged, unless strict.
+fn check_dataset_id(dataset: &NamedOrBlankNode, fdk_id: &Uuid, strict: bool) -> Result<(), Error> {
+    let NamedOrBlankNode::NamedNode(dataset) = dataset else {
+        return Ok(());
+    };
+    if dataset.as_str().contains(&fdk_id.to_string()) {
+        return Ok(());
+    }
+    if strict {
+        return Err(Error::Permanent(format!(
+            "dataset '{}' does not match FDK ID '{fdk_id}'",
+            dataset.as_str()
+        )));
+    }
+    tracing::warn!(dataset = dataset.as_str(), "dataset does not match FDK ID");
+    Ok(())
+}
+
+/// Skips events of types added upstream after this service, unless strict about event types.
+fn unknown_event_type(strict: bool) -> Result<(), Error> {
+    if strict {
+        return Err("unknown MqaEventType".into());
+    }
+    tracing::warn!("skipping event of unknown type");
+    Ok(())
+}
+
+/// Converts turtle assessment to json-ld, or skips the conversion if not emitting json-ld.
+fn jsonld_assessment(
+    assessment_graph: &AssessmentGraph,
+    turtle_assessment: &str,
+    emit_jsonld: bool,
+) -> Result<String, Error> {
+    if !emit_jsonld {
+        tracing::debug!("skipping json-ld conversion");
+        return Ok(String::new());
+    }
+    let start_time = Instant::now();
+    let jsonld = assessment_graph.turtle_to_jsonld(turtle_assessment)?;
+    tracing::debug!(
+        elapsed_millis = start_time.elapsed().as_millis(),
+        "converted assessment to json-ld"
+    );
+    Ok(jsonld)
+}
+
+/// Rejects event timestamps further than `max_skew_secs` ahead of `now`, as they would
+/// shadow all later events.
+fn validate_timestamp(timestamp: i64, now: i64, max_skew_secs: i64) -> Result<(), Error> {
+    if timestamp.saturating_sub(now) > max_skew_secs.saturating_mul(1000) {
+        Err(Error::Permanent(format!(
+            "event timestamp {timestamp} is more than {max_skew_secs} seconds ahead of current time {now}"
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Ensures serialized update fits within `max_bytes`, omitting the json-ld assessment if needed.
+fn limit_payload(mut update: UpdateRequest, max_bytes: usize) -> Result<UpdateRequest, Error> {
+    let size = serde_json::to_vec(&update)?.len();
+    if size <= max_bytes {
+        return Ok(update);
+    }
+
+    tracing::warn!(
+        size,
+        max_bytes,
+        "payload exceeds max size, omitting json-ld assessment"
+    );
+    update.jsonld_assessment = String::new();
+    let size = serde_json::to_vec(&update)?.len();
+    if size <= max_bytes {
+        Ok(update)
+    } else {
+        Err(Error::Permanent(format!(
+            "payload of {size} bytes exceeds max payload size of {max_bytes} bytes"
+        )))
+    }
+}
+
+/// Compact `dimension=score` listing of a score, formatted only when logged.
+struct DimensionScores<'a>(&'a Score);
+
+impl std::fmt::Display for DimensionScores<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{{")?;
+        for (i, dimension) in self.0.dimensions.iter().enumerate() {
+            let name = dimension
+                .id
+                .as_str()
+                .rsplit(['#', '/'])
+                .next()
+                .unwrap_or_default();
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", name, dimension.score)?;
         }
-        MqaEventType::Unknown => Err(format!("unknown MqaEventType").into()),
+        write!(f, "}}")
     }
 }
 
-async fn get_graph(client: &reqwest::Client, fdk_id: &Uuid) -> Result<Option<String>, Error> {
-    let response = client
-        .get(format!(
-            "{}/api/assessments/{fdk_id}",
-            SCORING_API_URL.clone()
-        ))
-        .send()
-        .await?;
+/// Logs dataset score of each dimension, and total, at debug level.
+fn log_score_breakdown(score_definitions: &ScoreDefinitions, dataset_score: &Score) {
+    if !tracing::enabled!(Level::DEBUG) {
+        return;
+    }
+    for (definition, dimension) in score_definitions
+        .dimensions
+        .iter()
+        .zip(dataset_score.dimensions.iter())
+    {
+        tracing::debug!(
+            dimension = dimension.id.as_str(),
+            score = dimension.score,
+            max_score = definition.total_score,
+            "dimension score"
+        );
+    }
+    tracing::debug!(
+        score = dataset_score.score,
+        max_score = score_definitions.total_score,
+        "total score"
+    );
+}
+
+/// Builds the scoring api url of an assessment, substituting `{id}` in the path template.
+pub(crate) fn assessment_url(api_url: &str, path_template: &str, fdk_id: &Uuid) -> String {
+    format!(
+        "{api_url}{}",
+        path_template.replace("{id}", &fdk_id.to_string())
+    )
+}
+
+/// Sends request, observing its duration by endpoint and response status.
+async fn timed_request(
+    endpoint: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, Error> {
+    let start_time = Instant::now();
+    let result = request.send().await;
+    let status = match &result {
+        Ok(response) => response.status().as_u16().to_string(),
+        Err(_) => "error".to_string(),
+    };
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[endpoint, &status])
+        .observe(start_time.elapsed().as_secs_f64());
+    Ok(result?)
+}
+
+/// Loads the existing assessment graph of a dataset, returning whether one exists.
+/// Gzip encoded responses are requested, and decompressed by the http client.
+/// The body is parsed as it is streamed, without reading all of it into memory first.
+/// Server errors and failed requests are retried a few times, before failing the message.
+async fn get_graph(
+    config: &Config,
+    client: &reqwest::Client,
+    fdk_id: &Uuid,
+    assessment_graph: &AssessmentGraph,
+) -> Result<bool, Error> {
+    let mut attempts = 0;
+    let response = loop {
+        attempts += 1;
+        let result = timed_request(
+            "get",
+            client.get(assessment_url(
+                &config.scoring_api_url,
+                &config.scoring_api_path,
+                fdk_id,
+            )),
+        )
+        .await;
+
+        if attempts >= 3 || !is_transient(&result) {
+            break result?;
+        }
+        tracing::warn!(attempts, "unable to get assessment graph, retrying");
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    };
 
     match response.status() {
-        StatusCode::NOT_FOUND => Ok(None),
-        StatusCode::OK => Ok(Some(response.text().await?)),
+        StatusCode::NOT_FOUND => Ok(false),
+        StatusCode::OK => {
+            let body = StreamReader::new(Box::pin(
+                response
+                    .bytes_stream()
+                    .map(|chunk| chunk.map_err(std::io::Error::other)),
+            ));
+            let body = BufReader::new(SyncIoBridge::new(body));
+            // Parsing blocks on reading the body, which is received on the runtime meanwhile.
+            let assessment_graph = assessment_graph.clone();
+            tokio::task::spawn_blocking(move || assessment_graph.load_reader(body))
+                .await
+                .map_err(|e| format!("unable to load assessment graph: {e}"))??;
+            Ok(true)
+        }
         _ => Err(format!(
             "Invalid response from scoring api: {} - {}",
             response.status(),
@@ -303,22 +921,36 @@ async fn get_graph(client: &reqwest::Client, fdk_id: &Uuid) -> Result<Option<Str
     }
 }
 
+/// Whether a request failed in a way that may succeed if sent again,
+/// i.e. it was not sent or timed out, or the server responded with an error.
+fn is_transient(result: &Result<reqwest::Response, Error>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(_) => true,
+    }
+}
+
 async fn post_scores(
+    config: &Config,
     client: &reqwest::Client,
     fdk_id: &Uuid,
     update: UpdateRequest,
 ) -> Result<(), Error> {
-    let response = client
-        .post(format!(
-            "{}/api/assessments/{fdk_id}",
-            SCORING_API_URL.clone()
-        ))
-        .header("X-API-KEY", SCORING_API_KEY.clone())
-        .json(&update)
-        .send()
-        .await?;
+    let response = timed_request(
+        "post",
+        client
+            .post(assessment_url(
+                &config.scoring_api_url,
+                &config.scoring_api_path,
+                fdk_id,
+            ))
+            .header("X-API-KEY", config.scoring_api_key.clone())
+            .json(&update),
+    )
+    .await?;
 
-    if response.status() == StatusCode::ACCEPTED {
+    if response.status() == StatusCode::OK || response.status() == StatusCode::ACCEPTED {
+        tracing::debug!(status = response.status().as_u16(), "scores posted");
         Ok(())
     } else {
         if response.status() == StatusCode::PAYLOAD_TOO_LARGE {
@@ -332,3 +964,937 @@ async fn post_scores(
         .into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, io::Write};
+
+    use super::*;
+    use crate::{
+        compare::canonical_triples,
+        config::Sink,
+        score::{calculate_score, MissingMeasurements},
+        score_graph::ScoreGraph,
+        test::{MEASUREMENT_GRAPH, METRIC_GRAPH, SCORE_GRAPH},
+        testkit::{CapturedEvents, MockScoringApi},
+        vocab::dcat_terms,
+    };
+    use flate2::{write::GzEncoder, Compression};
+    use httptest::{matchers::request, responders, Expectation, Server};
+    use oxigraph::model::{BlankNode, NamedNode};
+    use rdkafka::{message::OwnedMessage, Timestamp};
+    use tracing_subscriber::{layer::SubscriberExt, registry};
+
+    #[test]
+    fn jsonld_disabled() {
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        // Not valid turtle, so conversion would fail if attempted.
+        assert_eq!(
+            jsonld_assessment(&assessment_graph, "<a> <b>", false).unwrap(),
+            ""
+        );
+        assert!(jsonld_assessment(&assessment_graph, "<a> <b>", true).is_err());
+    }
+
+    #[test]
+    fn future_timestamp() {
+        let now = 1_700_000_000_000;
+        let day_secs = 24 * 60 * 60;
+
+        assert!(validate_timestamp(now - 1000, now, day_secs).is_ok());
+        assert!(validate_timestamp(now + 60 * 1000, now, day_secs).is_ok());
+        assert!(
+            validate_timestamp(now + 365 * day_secs * 1000, now, day_secs)
+                .unwrap_err()
+                .is_permanent()
+        );
+    }
+
+    #[test]
+    fn extreme_timestamp() {
+        let now = 1_700_000_000_000;
+        let day_secs = 24 * 60 * 60;
+
+        assert!(validate_timestamp(i64::MIN, now, day_secs).is_ok());
+        assert!(validate_timestamp(i64::MAX, now, day_secs)
+            .unwrap_err()
+            .is_permanent());
+        assert!(validate_timestamp(i64::MIN, i64::MAX, day_secs).is_ok());
+        assert!(validate_timestamp(i64::MAX, i64::MIN, day_secs)
+            .unwrap_err()
+            .is_permanent());
+    }
+
+    #[test]
+    fn lag_calculation() {
+        assert_eq!(consumer_lag(Some(90), 0, 100), 10);
+        assert_eq!(consumer_lag(Some(100), 0, 100), 0);
+        assert_eq!(consumer_lag(None, 20, 100), 80);
+        // Committed offset removed by retention.
+        assert_eq!(consumer_lag(Some(10), 20, 100), 80);
+        assert_eq!(consumer_lag(Some(110), 0, 100), 0);
+    }
+
+    #[test]
+    fn offset_store_decision() {
+        let failed: Result<(), Error> = Err("failed".into());
+
+        assert!(should_store_offset(&Ok(()), false));
+        assert!(should_store_offset(&Ok(()), true));
+        assert!(should_store_offset(&failed, false));
+        assert!(!should_store_offset(&failed, true));
+    }
+
+    fn update_request(jsonld_assessment: String) -> UpdateRequest {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        assessment_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&assessment_graph, &score_definitions).unwrap();
+        UpdateRequest {
+            scores: convert_scores(
+                &score_definitions,
+                &[vec![dataset_score], distribution_scores].concat(),
+            )
+            .unwrap(),
+            turtle_assessment: "turtle".to_string(),
+            jsonld_assessment,
+            partial: false,
+        }
+    }
+
+    #[test]
+    fn payload_size_limit() {
+        let update = || update_request("x".repeat(1000));
+        let size = serde_json::to_vec(&update()).unwrap().len();
+
+        let below = limit_payload(update(), size).unwrap();
+        assert_eq!(below.jsonld_assessment.len(), 1000);
+
+        let without_jsonld = limit_payload(update(), size - 1).unwrap();
+        assert_eq!(without_jsonld.jsonld_assessment, "");
+
+        assert!(limit_payload(update(), size - 1001)
+            .unwrap_err()
+            .is_permanent());
+    }
+
+    #[tokio::test]
+    async fn request_duration() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/timed"))
+                .respond_with(responders::status_code(418)),
+        );
+        let observations = || {
+            HTTP_REQUEST_DURATION
+                .with_label_values(&["test", "418"])
+                .get_sample_count()
+        };
+        let before = observations();
+
+        let response = timed_request("test", reqwest::Client::new().get(server.url_str("/timed")))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(observations(), before + 1);
+    }
+
+    #[tokio::test]
+    async fn request_timeout() {
+        let server = Server::run();
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/slow")).respond_with(
+                responders::delay_and_then(Duration::from_secs(5), responders::status_code(200)),
+            ),
+        );
+
+        let client = build_http_client(Duration::from_millis(100)).unwrap();
+        let result = tokio::time::timeout(
+            Duration::from_secs(2),
+            client.get(server.url_str("/slow")).send(),
+        )
+        .await
+        .expect("request hung past its timeout");
+
+        match result.map_err(Error::from) {
+            Err(Error::ReqwestError(e)) => assert!(e.is_timeout()),
+            other => panic!("expected request timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dimension_scores_span_field() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        assessment_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, _) = calculate_score(&assessment_graph, &score_definitions).unwrap();
+
+        let events = CapturedEvents::default();
+        tracing::subscriber::with_default(registry().with(events.clone()), || {
+            let span = tracing::info_span!("event", dimension_scores = tracing::field::Empty);
+            span.record(
+                "dimension_scores",
+                tracing::field::display(DimensionScores(&dataset_score)),
+            );
+        });
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].get("dimension_scores").map(String::as_str),
+            Some("{accessibility=70, interoperability=0}")
+        );
+    }
+
+    #[test]
+    fn score_breakdown_log() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        assessment_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, _) = calculate_score(&assessment_graph, &score_definitions).unwrap();
+
+        let events = CapturedEvents::default();
+        tracing::subscriber::with_default(registry().with(events.clone()), || {
+            log_score_breakdown(&score_definitions, &dataset_score)
+        });
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0].get("dimension").map(String::as_str),
+            Some("https://data.norge.no/vocabulary/dcatno-mqa#accessibility")
+        );
+        assert_eq!(events[0].get("score").map(String::as_str), Some("70"));
+        assert_eq!(events[0].get("max_score").map(String::as_str), Some("70"));
+        assert_eq!(
+            events[2].get("message").map(String::as_str),
+            Some("total score")
+        );
+        assert_eq!(events[2].get("score").map(String::as_str), Some("70"));
+        assert_eq!(events[2].get("max_score").map(String::as_str), Some("90"));
+    }
+
+    #[tokio::test]
+    async fn decode_error_context() {
+        let mut decoder = AvroDecoder::new(SrSettings::new("http://localhost:8081".to_string()));
+        let message = OwnedMessage::new(
+            Some(b"not avro".to_vec()),
+            None,
+            "mqa-events".to_string(),
+            Timestamp::NotAvailable,
+            2,
+            42,
+            None,
+        );
+
+        let events = CapturedEvents::default();
+        let guard = tracing::subscriber::set_default(registry().with(events.clone()));
+        assert!(decode_message(&mut decoder, &message, &[], true)
+            .await
+            .is_err());
+        drop(guard);
+
+        let events = events.0.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].get("message").map(String::as_str),
+            Some("unable to decode message")
+        );
+        assert_eq!(
+            events[0].get("topic").map(String::as_str),
+            Some("mqa-events")
+        );
+        assert_eq!(events[0].get("partition").map(String::as_str), Some("2"));
+        assert_eq!(events[0].get("offset").map(String::as_str), Some("42"));
+        assert!(events[0].contains_key("error"));
+        assert_eq!(
+            events[1].get("payload").map(String::as_str),
+            Some("bm90IGF2cm8=")
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_payload_permanent() {
+        let mut decoder = AvroDecoder::new(SrSettings::new("http://localhost:8081".to_string()));
+        let Err(err) = decode_payload(&mut decoder, Some(b"not avro"), &[]).await else {
+            panic!("invalid payload decoded");
+        };
+        assert!(err.is_permanent());
+        assert!(err.to_string().contains("unable to decode message"));
+    }
+
+    #[test]
+    fn decode_error_classification() {
+        let decoder = AvroDecoder::new(SrSettings::new("http://localhost:8081".to_string()));
+
+        let connectivity = SRCError::retryable_with_cause(
+            "error sending request: connection refused",
+            "http call to schema registry failed",
+        );
+        let err = classify_decode_error(&decoder, connectivity);
+        assert!(matches!(err, Error::SRCError(_)));
+        assert!(should_retry(&Err(err)));
+
+        let registry_response = SRCError::non_retryable_with_cause(
+            "expected value at line 1 column 1",
+            "could not parse to RawRegisteredSchema, schema might not exist on this schema registry, the http call failed, cause will give more information",
+        );
+        let err = classify_decode_error(&decoder, registry_response);
+        assert!(matches!(err, Error::SRCError(_)));
+        assert!(should_retry(&Err(err)));
+
+        let invalid_bytes = SRCError::non_retryable_without_cause("Invalid bytes [1, 0, 0, 0, 1]");
+        let err = classify_decode_error(&decoder, invalid_bytes);
+        assert!(err.is_permanent());
+        assert!(!should_retry(&Err(err)));
+
+        let value = SRCError::non_retryable_with_cause(
+            "unexpected end of input",
+            "Could not transform bytes using schema",
+        );
+        let err = classify_decode_error(&decoder, value);
+        assert!(err.is_permanent());
+        assert!(!should_retry(&Err(err)));
+    }
+
+    #[tokio::test]
+    async fn empty_payload_skipped() {
+        let mut decoder = AvroDecoder::new(SrSettings::new("http://localhost:8081".to_string()));
+        assert!(matches!(
+            decode_payload(&mut decoder, None, &[]).await,
+            Ok(InputEvent::Empty)
+        ));
+        assert!(matches!(
+            decode_payload(&mut decoder, Some(&[]), &[]).await,
+            Ok(InputEvent::Empty)
+        ));
+    }
+
+    /// Config read from `vars` only, independent of the environment of the test runner.
+    fn config(vars: &[(&str, &str)]) -> Config {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        Config::from_lookup(|name| vars.get(name).cloned()).unwrap()
+    }
+
+    fn legacy_event_value(event_type: &str) -> Value {
+        Value::Record(vec![
+            ("type".to_string(), Value::Enum(0, event_type.to_string())),
+            ("fdkId".to_string(), Value::String("foo".to_string())),
+            (
+                "graph".to_string(),
+                Value::String("<a> <b> <c> .".to_string()),
+            ),
+            ("timestamp".to_string(), Value::Long(1647698566000)),
+        ])
+    }
+
+    #[test]
+    fn legacy_event() {
+        let value = legacy_event_value("DATASET_HARVESTED");
+        let config = config(&[("LEGACY_EVENTS", "no.fdk.dataset.DatasetEvent")]);
+
+        match input_event(
+            &config.legacy_events,
+            "no.fdk.dataset".to_string(),
+            "DatasetEvent".to_string(),
+            &value,
+        ) {
+            Ok(InputEvent::MqaEvent(event)) => assert_eq!(
+                event,
+                MqaEvent {
+                    event_type: MqaEventType::PropertiesChecked,
+                    fdk_id: "foo".to_string(),
+                    graph: "<a> <b> <c> .".to_string(),
+                    timestamp: 1647698566000,
+                }
+            ),
+            _ => panic!("legacy event not converted to mqa event"),
+        }
+    }
+
+    #[test]
+    fn legacy_event_skipped_by_default() {
+        let value = legacy_event_value("DATASET_HARVESTED");
+        assert!(matches!(
+            input_event(
+                &config(&[]).legacy_events,
+                "no.fdk.dataset".to_string(),
+                "DatasetEvent".to_string(),
+                &value,
+            ),
+            Ok(InputEvent::Unknown { .. })
+        ));
+    }
+
+    #[test]
+    fn transient_error_retried() {
+        assert!(should_retry(&Err("unavailable".into())));
+        assert!(!should_retry(&Err(Error::Permanent("invalid".to_string()))));
+        assert!(!should_retry(&Ok(())));
+    }
+
+    #[tokio::test]
+    async fn invalid_fdk_id_not_retried() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        let config = config(&[]);
+        let http_client = create_http_client(&config).unwrap();
+
+        let result = handle_mqa_event(
+            &config,
+            &score_definitions,
+            &assessment_graph,
+            &http_client,
+            None,
+            MqaEvent {
+                event_type: MqaEventType::PropertiesChecked,
+                fdk_id: "foo".to_string(),
+                graph: "<a> <b> <c> .".to_string(),
+                timestamp: 1647698566000,
+            },
+        )
+        .await;
+
+        assert!(!should_retry(&result));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("unable to parse FDK ID 'foo'"));
+    }
+
+    #[test]
+    fn mismatched_dataset_id() {
+        let fdk_id = Uuid::parse_str("6e4237cb-b3b4-4d6f-a8c1-7f2d8b3a9f10").unwrap();
+        let dataset = |iri: &str| NamedOrBlankNode::from(NamedNode::new_unchecked(iri));
+
+        let matching =
+            dataset("https://data.norge.no/datasets/6e4237cb-b3b4-4d6f-a8c1-7f2d8b3a9f10");
+        assert!(check_dataset_id(&matching, &fdk_id, true).is_ok());
+
+        let mismatched =
+            dataset("https://data.norge.no/datasets/0a6bd1f6-2b4e-4d8a-9c3f-1e5d7b9a2c40");
+        assert!(check_dataset_id(&mismatched, &fdk_id, false).is_ok());
+        let err = check_dataset_id(&mismatched, &fdk_id, true).unwrap_err();
+        assert!(err.is_permanent());
+        assert!(err.to_string().contains("does not match FDK ID"));
+
+        let blank = NamedOrBlankNode::from(BlankNode::default());
+        assert!(check_dataset_id(&blank, &fdk_id, true).is_ok());
+    }
+
+    #[test]
+    fn unknown_event_type_skipped() {
+        assert!(unknown_event_type(false).is_ok());
+        assert_eq!(
+            unknown_event_type(true).unwrap_err().to_string(),
+            "unknown MqaEventType"
+        );
+    }
+
+    #[test]
+    fn commit_mode() {
+        assert!(kafka_commit_mode(OffsetCommitMode::Store).is_none());
+        assert!(matches!(
+            kafka_commit_mode(OffsetCommitMode::Sync),
+            Some(CommitMode::Sync)
+        ));
+        assert!(matches!(
+            kafka_commit_mode(OffsetCommitMode::Async),
+            Some(CommitMode::Async)
+        ));
+    }
+
+    #[tokio::test]
+    async fn filtered_event_type() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        assessment_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let event = MqaEvent::builder()
+            .graph(MEASUREMENT_GRAPH)
+            .timestamp(1647698566000)
+            .build()
+            .unwrap();
+
+        let config = config(&[("PROCESS_EVENT_TYPES", "URLS_CHECKED")]);
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &assessment_graph,
+            &reqwest::Client::new(),
+            None,
+            event,
+        )
+        .await
+        .unwrap();
+
+        // Skipped before the graph is cleared, fetched or posted.
+        assert!(assessment_graph.dataset().is_ok());
+    }
+
+    #[tokio::test]
+    async fn gzipped_graph() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(MEASUREMENT_GRAPH.as_bytes()).unwrap();
+        api.serve_encoded_graph(&fdk_id, Some("gzip"), encoder.finish().unwrap());
+
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        let http_client = create_http_client(&config).unwrap();
+        let found = get_graph(&config, &http_client, &fdk_id, &assessment_graph)
+            .await
+            .unwrap();
+        assert!(found);
+        assert_eq!(
+            assessment_graph.dataset().unwrap().assessment.as_str(),
+            "https://dataset.assessment.foo"
+        );
+        assert_eq!(
+            api.calls_to("GET", &fdk_id)[0].header("accept-encoding"),
+            Some("gzip")
+        );
+    }
+
+    #[tokio::test]
+    async fn get_graph_retried() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        let prior_graph = AssessmentGraph::new().unwrap();
+        prior_graph
+            .load(MEASUREMENT_GRAPH.replace("\"200\"", "\"404\""))
+            .unwrap();
+        prior_graph
+            .insert_modified_timestmap(1647698565000)
+            .unwrap();
+        api.serve_graph(&fdk_id, &prior_graph.to_turtle().unwrap());
+        api.queue_statuses("GET", &fdk_id, &[503, 502]);
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let result = handle_mqa_event(
+            &config,
+            &score_definitions,
+            &AssessmentGraph::new().unwrap(),
+            &create_http_client(&config).unwrap(),
+            None,
+            MqaEvent::builder()
+                .fdk_id(fdk_id)
+                .graph(MEASUREMENT_GRAPH)
+                .timestamp(1647698566000)
+                .build()
+                .unwrap(),
+        )
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(api.calls_to("GET", &fdk_id).len(), 3);
+        assert_eq!(api.calls_to("POST", &fdk_id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn get_graph_not_retried_on_client_error() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        api.queue_statuses("GET", &fdk_id, &[400]);
+
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        let http_client = create_http_client(&config).unwrap();
+        let result = get_graph(&config, &http_client, &fdk_id, &assessment_graph).await;
+        assert!(result.unwrap_err().to_string().contains("400"));
+        assert_eq!(api.calls_to("GET", &fdk_id).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn custom_http_client() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-request-id", "abc".parse().unwrap());
+        let http_client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap();
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &AssessmentGraph::new().unwrap(),
+            &http_client,
+            None,
+            MqaEvent::builder()
+                .fdk_id(fdk_id)
+                .graph(MEASUREMENT_GRAPH)
+                .timestamp(1647698566000)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let calls = api.calls();
+        assert_eq!(calls.len(), 2);
+        assert!(calls
+            .iter()
+            .all(|call| call.header("x-request-id") == Some("abc")));
+        api.assert_posted(&fdk_id);
+    }
+
+    #[tokio::test]
+    async fn post_scores_ok() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        api.queue_statuses("POST", &fdk_id, &[200]);
+
+        let result = post_scores(
+            &config,
+            &create_http_client(&config).unwrap(),
+            &fdk_id,
+            update_request(String::new()),
+        )
+        .await;
+        assert!(result.is_ok());
+        api.assert_posted(&fdk_id);
+    }
+
+    #[tokio::test]
+    async fn post_scores_conflict() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        api.queue_statuses("POST", &fdk_id, &[409]);
+
+        let result = post_scores(
+            &config,
+            &create_http_client(&config).unwrap(),
+            &fdk_id,
+            update_request(String::new()),
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("409"));
+    }
+
+    #[tokio::test]
+    async fn retries_start_from_clear_graph() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        api.queue_statuses("POST", &fdk_id, &[503, 503, 503]);
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        let http_client = create_http_client(&config).unwrap();
+        for _ in 0..3 {
+            // Leftovers of e.g. an attempt that failed before clearing the graph.
+            assessment_graph
+                .load("<https://leftover> <https://leftover> <https://leftover> .")
+                .unwrap();
+            let result = handle_mqa_event(
+                &config,
+                &score_definitions,
+                &assessment_graph,
+                &http_client,
+                None,
+                MqaEvent::builder()
+                    .fdk_id(fdk_id)
+                    .graph(MEASUREMENT_GRAPH)
+                    .timestamp(1647698566000)
+                    .build()
+                    .unwrap(),
+            )
+            .await;
+            assert!(should_retry(&result));
+            assert!(assessment_graph.is_empty().unwrap());
+        }
+
+        // Every attempt posts the same graph, without leftovers or the scores of earlier attempts.
+        let posted = api
+            .calls_to("POST", &fdk_id)
+            .iter()
+            .map(|call| serde_json::from_slice::<UpdateRequest>(&call.body).unwrap())
+            .map(|update| canonical_triples(&update.turtle_assessment).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(posted.len(), 3);
+        assert!(posted
+            .iter()
+            .all(|triples| triples.len() == posted[0].len()));
+        assert!(posted
+            .iter()
+            .flatten()
+            .all(|triple| !triple.contains("leftover")));
+        assert_eq!(api.calls_to("GET", &fdk_id).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn unmeasured_assessment_keeps_prior_scores() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        let prior_graph = AssessmentGraph::new().unwrap();
+        prior_graph.load(MEASUREMENT_GRAPH).unwrap();
+        prior_graph
+            .insert_modified_timestmap(1647698566000)
+            .unwrap();
+        api.serve_graph(&fdk_id, &prior_graph.to_turtle().unwrap());
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let event = MqaEvent::builder()
+            .fdk_id(fdk_id)
+            .graph(r#"
+                <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
+            "#)
+            .timestamp(1647698567000)
+            .build()
+            .unwrap();
+
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &AssessmentGraph::new().unwrap(),
+            &create_http_client(&config).unwrap(),
+            None,
+            event,
+        )
+        .await
+        .unwrap();
+        api.assert_not_posted(&fdk_id);
+    }
+
+    #[tokio::test]
+    async fn unchanged_measurements_not_reposted() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        // Stored as posted when last scored.
+        let prior_graph = AssessmentGraph::new().unwrap();
+        prior_graph.load(MEASUREMENT_GRAPH).unwrap();
+        prior_graph
+            .insert_modified_timestmap(1647698566000)
+            .unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&prior_graph, &score_definitions).unwrap();
+        prior_graph
+            .insert_scores(&[vec![dataset_score], distribution_scores].concat())
+            .unwrap();
+        api.serve_graph(&fdk_id, &prior_graph.to_turtle().unwrap());
+
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &AssessmentGraph::new().unwrap(),
+            &create_http_client(&config).unwrap(),
+            None,
+            MqaEvent::builder()
+                .fdk_id(fdk_id)
+                .graph(MEASUREMENT_GRAPH)
+                .timestamp(1647698567000)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(api.calls_to("GET", &fdk_id).len(), 1);
+        api.assert_not_posted(&fdk_id);
+    }
+
+    #[tokio::test]
+    async fn kafka_sink_ignores_api() {
+        let api = MockScoringApi::start();
+        let config = Config {
+            sink: Sink::Kafka,
+            output_topic: Some("mqa-scores".to_string()),
+            ..api.config()
+        };
+        let fdk_id = Uuid::new_v4();
+        // Would be skipped as outdated, if read.
+        let prior_graph = AssessmentGraph::new().unwrap();
+        prior_graph.load(MEASUREMENT_GRAPH).unwrap();
+        prior_graph
+            .insert_modified_timestmap(1647698567000)
+            .unwrap();
+        api.serve_graph(&fdk_id, &prior_graph.to_turtle().unwrap());
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &assessment_graph,
+            &create_http_client(&config).unwrap(),
+            None,
+            MqaEvent::builder()
+                .fdk_id(fdk_id)
+                .graph(MEASUREMENT_GRAPH)
+                .timestamp(1647698566000)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(api.calls(), vec![]);
+    }
+
+    #[tokio::test]
+    async fn missing_measurements_policy_not_in_graph() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let mut posted = Vec::new();
+        for missing_measurements in [MissingMeasurements::Unscored, MissingMeasurements::Zero] {
+            let api = MockScoringApi::start();
+            let config = Config {
+                missing_measurements,
+                ..api.config()
+            };
+            let fdk_id = Uuid::new_v4();
+            handle_mqa_event(
+                &config,
+                &score_definitions,
+                &AssessmentGraph::new().unwrap(),
+                &create_http_client(&config).unwrap(),
+                None,
+                MqaEvent::builder()
+                    .fdk_id(fdk_id)
+                    .graph(MEASUREMENT_GRAPH)
+                    .timestamp(1647698566000)
+                    .build()
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+            posted.push(api.assert_posted(&fdk_id));
+        }
+
+        // Provenance holds the time of scoring, which differs between the two.
+        let triples = |update: &UpdateRequest| {
+            canonical_triples(&update.turtle_assessment)
+                .unwrap()
+                .into_iter()
+                .filter(|triple| !triple.contains(&dcat_terms::CREATED.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(triples(&posted[0]), triples(&posted[1]));
+        assert_ne!(posted[0].scores, posted[1].scores);
+    }
+
+    #[tokio::test]
+    async fn graph_cleared_after_handling() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let assessment_graph = AssessmentGraph::new().unwrap();
+        handle_mqa_event(
+            &config,
+            &score_definitions,
+            &assessment_graph,
+            &create_http_client(&config).unwrap(),
+            None,
+            MqaEvent::builder()
+                .fdk_id(fdk_id)
+                .graph(MEASUREMENT_GRAPH)
+                .timestamp(1647698566000)
+                .build()
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        api.assert_posted(&fdk_id);
+        assert!(assessment_graph.is_empty().unwrap());
+    }
+
+    #[tokio::test]
+    async fn incomplete_new_assessment() {
+        let api = MockScoringApi::start();
+        let config = api.config();
+        let fdk_id = Uuid::new_v4();
+
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let event = MqaEvent::builder()
+            .fdk_id(fdk_id)
+            .graph(r#"
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+                _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability> .
+                _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            "#)
+            .timestamp(1647698566000)
+            .build()
+            .unwrap();
+
+        let result = handle_mqa_event(
+            &config,
+            &score_definitions,
+            &AssessmentGraph::new().unwrap(),
+            &create_http_client(&config).unwrap(),
+            None,
+            event,
+        )
+        .await;
+        assert!(result.unwrap_err().is_permanent());
+        api.assert_not_posted(&fdk_id);
+    }
+
+    #[test]
+    fn custom_path_template() {
+        let template = "/gateway/scoring/{id}/assessment";
+        let fdk_id = Uuid::parse_str("0b5a3a2e-6b2e-4ba6-9a43-7d5e7f3b0c11").unwrap();
+
+        assert_eq!(
+            assessment_url("http://localhost:8082", template, &fdk_id),
+            "http://localhost:8082/gateway/scoring/0b5a3a2e-6b2e-4ba6-9a43-7d5e7f3b0c11/assessment"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 pub mod assessment_graph;
+pub mod compare;
+pub mod config;
 pub mod error;
 pub mod helpers;
 pub mod json_conversion;
@@ -6,7 +8,9 @@ pub mod kafka;
 mod measurement_value;
 pub mod metrics;
 pub mod schemas;
-mod score;
+pub mod score;
 pub mod score_graph;
 mod test;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
 pub mod vocab;
@@ -1,12 +1,21 @@
+use chrono::{DateTime, NaiveDateTime};
 use oxigraph::model::{vocab::xsd, Literal};
 
 use crate::error::Error;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum MeasurementValue {
     Bool(bool),
     Int(i64),
+    /// Timestamp as epoch millis.
+    DateTime(i64),
     String(String),
+    /// URL-valued measurement, e.g. a licence document. Not validated until scored.
+    Uri(String),
+    LangString {
+        value: String,
+        lang: String,
+    },
     Unknown(String),
 }
 
@@ -15,15 +24,185 @@ impl TryFrom<Literal> for MeasurementValue {
 
     /// Try to parse quality measurement value from graph store literal.
     fn try_from(value: Literal) -> Result<Self, Self::Error> {
+        if let Some(lang) = value.language() {
+            return Ok(Self::LangString {
+                value: value.value().to_string(),
+                lang: lang.to_string(),
+            });
+        }
         match value.datatype() {
             xsd::STRING => Ok(Self::String(value.value().to_string())),
-            xsd::BOOLEAN => Ok(Self::Bool(value.value().parse().map_err(|_| {
+            xsd::ANY_URI => Ok(Self::Uri(value.value().to_string())),
+            xsd::BOOLEAN => Ok(Self::Bool(parse_bool(value.value()).ok_or_else(|| {
                 format!("unable to parse measurement bool: {}", value.value())
             })?)),
-            xsd::INTEGER => Ok(Self::Int(value.value().parse().map_err(|_| {
-                format!("unable to parse measurement int: {}", value.value())
-            })?)),
+            xsd::INTEGER
+            | xsd::INT
+            | xsd::LONG
+            | xsd::SHORT
+            | xsd::BYTE
+            | xsd::NON_NEGATIVE_INTEGER
+            | xsd::NON_POSITIVE_INTEGER
+            | xsd::POSITIVE_INTEGER
+            | xsd::NEGATIVE_INTEGER
+            | xsd::UNSIGNED_LONG
+            | xsd::UNSIGNED_INT
+            | xsd::UNSIGNED_SHORT
+            | xsd::UNSIGNED_BYTE => {
+                Ok(Self::Int(value.value().parse().map_err(|_| {
+                    format!("unable to parse measurement int: {}", value.value())
+                })?))
+            }
+            xsd::DATE_TIME | xsd::DATE_TIME_STAMP => {
+                Ok(Self::DateTime(parse_date_time(value.value()).ok_or_else(
+                    || format!("unable to parse measurement dateTime: {}", value.value()),
+                )?))
+            }
             _ => Ok(Self::Unknown(value.value().to_string())),
         }
     }
 }
+
+/// Parses xsd:boolean, i.e. `true`, `false`, `1` or `0`.
+/// Case is ignored, as some producers emit e.g. `TRUE`.
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses xsd:dateTime as epoch millis, assuming UTC when timezone is absent.
+fn parse_date_time(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date_time| date_time.timestamp_millis())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|date_time| date_time.and_utc().timestamp_millis())
+        })
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lang_string() {
+        assert_eq!(
+            MeasurementValue::try_from(
+                Literal::new_language_tagged_literal("A dataset", "en").unwrap()
+            )
+            .unwrap(),
+            MeasurementValue::LangString {
+                value: "A dataset".to_string(),
+                lang: "en".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn plain_string() {
+        assert_eq!(
+            MeasurementValue::try_from(Literal::new_simple_literal("A dataset")).unwrap(),
+            MeasurementValue::String("A dataset".to_string())
+        );
+    }
+
+    #[test]
+    fn any_uri() {
+        for value in ["https://data.norge.no/nlod/no/2.0", "not a uri"] {
+            assert_eq!(
+                MeasurementValue::try_from(Literal::new_typed_literal(value, xsd::ANY_URI))
+                    .unwrap(),
+                MeasurementValue::Uri(value.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn integer_datatypes() {
+        for (value, datatype) in [
+            ("200", xsd::INTEGER),
+            ("200", xsd::INT),
+            ("200", xsd::LONG),
+            ("200", xsd::SHORT),
+            ("100", xsd::BYTE),
+            ("200", xsd::NON_NEGATIVE_INTEGER),
+            ("-200", xsd::NON_POSITIVE_INTEGER),
+            ("200", xsd::POSITIVE_INTEGER),
+            ("-200", xsd::NEGATIVE_INTEGER),
+            ("200", xsd::UNSIGNED_LONG),
+            ("200", xsd::UNSIGNED_INT),
+            ("200", xsd::UNSIGNED_SHORT),
+            ("200", xsd::UNSIGNED_BYTE),
+        ] {
+            assert_eq!(
+                MeasurementValue::try_from(Literal::new_typed_literal(value, datatype)).unwrap(),
+                MeasurementValue::Int(value.parse().unwrap()),
+                "{}",
+                datatype
+            );
+        }
+    }
+
+    #[test]
+    fn bool_forms() {
+        for (value, expected) in [
+            ("true", true),
+            ("false", false),
+            ("1", true),
+            ("0", false),
+            ("TRUE", true),
+            ("False", false),
+            (" true ", true),
+        ] {
+            assert_eq!(
+                MeasurementValue::try_from(Literal::new_typed_literal(value, xsd::BOOLEAN))
+                    .unwrap(),
+                MeasurementValue::Bool(expected),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_bool() {
+        for value in ["yes", "2", "", "truee"] {
+            let err = MeasurementValue::try_from(Literal::new_typed_literal(value, xsd::BOOLEAN))
+                .unwrap_err();
+            assert!(err.to_string().contains("unable to parse measurement bool"));
+        }
+    }
+
+    #[test]
+    fn date_time() {
+        assert_eq!(
+            MeasurementValue::try_from(Literal::new_typed_literal(
+                "2022-06-27T08:01:52.123Z",
+                xsd::DATE_TIME_STAMP
+            ))
+            .unwrap(),
+            MeasurementValue::DateTime(1656316912123)
+        );
+        assert_eq!(
+            MeasurementValue::try_from(Literal::new_typed_literal(
+                "2022-06-27T10:01:52.123+02:00",
+                xsd::DATE_TIME
+            ))
+            .unwrap(),
+            MeasurementValue::DateTime(1656316912123)
+        );
+    }
+
+    #[test]
+    fn malformed_date_time() {
+        assert!(MeasurementValue::try_from(Literal::new_typed_literal(
+            "27.06.2022 08:01",
+            xsd::DATE_TIME_STAMP
+        ))
+        .is_err());
+    }
+}
@@ -1,5 +1,10 @@
+use std::{collections::HashSet, sync::Mutex};
+
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounterVec, Opts, Registry};
+use prometheus::{
+    proto::MetricType, Encoder, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec,
+    Opts, Registry,
+};
 
 use crate::error::Error;
 
@@ -21,8 +26,42 @@ lazy_static! {
         tracing::error!(error = e.to_string(), "processing_time");
         std::process::exit(1);
     });
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = HistogramVec::new(
+        HistogramOpts {
+            common_opts: Opts::new("http_request_duration", "Scoring API Request Durations"),
+            buckets: vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 30.0],
+        },
+        &["endpoint", "status"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "http_request_duration metric error");
+        std::process::exit(1);
+    });
+    pub static ref UNKNOWN_METRICS: IntCounterVec = IntCounterVec::new(
+        Opts::new(
+            "unknown_metrics",
+            "Measured Metrics Without Score Definition"
+        ),
+        &["metric"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "unknown_metrics metric error");
+        std::process::exit(1);
+    });
+    pub static ref CONSUMER_LAG: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("consumer_lag", "Consumer Lag"),
+        &["topic", "partition"]
+    )
+    .unwrap_or_else(|e| {
+        tracing::error!(error = e.to_string(), "consumer_lag metric error");
+        std::process::exit(1);
+    });
+    static ref UNKNOWN_METRIC_LABELS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
 }
 
+/// Max number of distinct metric labels of `UNKNOWN_METRICS`, beyond which "other" is used.
+const MAX_UNKNOWN_METRIC_LABELS: usize = 100;
+
 pub fn register_metrics() {
     REGISTRY
         .register(Box::new(PROCESSED_MESSAGES.clone()))
@@ -37,15 +76,208 @@ pub fn register_metrics() {
             tracing::error!(error = e.to_string(), "response_time collector error");
             std::process::exit(1);
         });
+
+    REGISTRY
+        .register(Box::new(HTTP_REQUEST_DURATION.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(
+                error = e.to_string(),
+                "http_request_duration collector error"
+            );
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(UNKNOWN_METRICS.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "unknown_metrics collector error");
+            std::process::exit(1);
+        });
+
+    REGISTRY
+        .register(Box::new(CONSUMER_LAG.clone()))
+        .unwrap_or_else(|e| {
+            tracing::error!(error = e.to_string(), "consumer_lag collector error");
+            std::process::exit(1);
+        });
+}
+
+/// Counts a measured metric that has no score definition.
+pub fn inc_unknown_metric(metric: &str) {
+    let mut labels = UNKNOWN_METRIC_LABELS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let label = if labels.contains(metric) {
+        metric
+    } else if labels.len() < MAX_UNKNOWN_METRIC_LABELS {
+        labels.insert(metric.to_string());
+        metric
+    } else {
+        "other"
+    };
+    UNKNOWN_METRICS.with_label_values(&[label]).inc();
+}
+
+/// Exposition format of gathered metrics.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MetricsFormat {
+    #[default]
+    Prometheus,
+    OpenMetrics,
+}
+
+impl MetricsFormat {
+    /// Format requested by an `Accept` header, Prometheus text unless OpenMetrics is accepted
+    /// with at least the quality of plain text. Media ranges with `q=0` are not accepted.
+    pub fn from_accept(accept: Option<&str>) -> Self {
+        let (mut open_metrics, mut prometheus) = (0.0_f32, 0.0_f32);
+        for range in accept.unwrap_or_default().split(',') {
+            let mut parts = range.split(';');
+            let media_type = parts.next().unwrap_or_default().trim().to_lowercase();
+            let quality = parts
+                .map(|param| param.trim().to_lowercase())
+                .find_map(|param| param.strip_prefix("q=").map(|q| q.parse().unwrap_or(0.0)))
+                .unwrap_or(1.0);
+            match media_type.as_str() {
+                "application/openmetrics-text" => open_metrics = open_metrics.max(quality),
+                "text/plain" | "text/*" | "*/*" => prometheus = prometheus.max(quality),
+                _ => (),
+            }
+        }
+        if open_metrics > 0.0 && open_metrics >= prometheus {
+            Self::OpenMetrics
+        } else {
+            Self::Prometheus
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Prometheus => prometheus::TEXT_FORMAT,
+            Self::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
 }
 
 pub fn get_metrics() -> Result<String, Error> {
+    get_metrics_as(MetricsFormat::Prometheus)
+}
+
+pub fn get_metrics_as(format: MetricsFormat) -> Result<String, Error> {
+    encode_metrics(&REGISTRY.gather(), format)
+}
+
+fn encode_metrics(
+    families: &[prometheus::proto::MetricFamily],
+    format: MetricsFormat,
+) -> Result<String, Error> {
     let mut buffer = Vec::new();
 
     prometheus::TextEncoder::new()
-        .encode(&REGISTRY.gather(), &mut buffer)
+        .encode(families, &mut buffer)
         .map_err(|e| e.to_string())?;
 
     let metrics = String::from_utf8(buffer).map_err(|e| e.to_string())?;
-    Ok(metrics)
+    match format {
+        MetricsFormat::Prometheus => Ok(metrics),
+        MetricsFormat::OpenMetrics => {
+            let counters: Vec<&str> = families
+                .iter()
+                .filter(|family| family.get_field_type() == MetricType::COUNTER)
+                .map(|family| family.get_name())
+                .collect();
+            Ok(to_open_metrics(&metrics, &counters))
+        }
+    }
+}
+
+/// Converts Prometheus text to OpenMetrics, which differs by counter samples being suffixed
+/// with `_total`, counter families being named without it, and by the `# EOF` trailer.
+fn to_open_metrics(metrics: &str, counters: &[&str]) -> String {
+    let mut open_metrics = String::new();
+    for line in metrics.lines() {
+        let (comment, sample) = ["# HELP ", "# TYPE "]
+            .into_iter()
+            .find_map(|comment| Some((comment, line.strip_prefix(comment)?)))
+            .unwrap_or(("", line));
+        let counter = counters.iter().find_map(|name| {
+            let rest = sample.strip_prefix(name)?;
+            rest.starts_with(['{', ' ']).then_some((*name, rest))
+        });
+        match counter {
+            Some((name, rest)) if !comment.is_empty() => {
+                let family = name.strip_suffix("_total").unwrap_or(name);
+                open_metrics.push_str(&format!("{comment}{family}{rest}"));
+            }
+            Some((name, rest)) if !name.ends_with("_total") => {
+                open_metrics.push_str(&format!("{name}_total{rest}"));
+            }
+            _ => open_metrics.push_str(line),
+        }
+        open_metrics.push('\n');
+    }
+    open_metrics.push_str("# EOF\n");
+    open_metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use prometheus::{IntCounter, IntGauge};
+
+    use super::*;
+
+    fn families() -> Vec<prometheus::proto::MetricFamily> {
+        let registry = Registry::new();
+        let counter =
+            IntCounterVec::new(Opts::new("handled", "Handled Messages"), &["status"]).unwrap();
+        counter.with_label_values(&["success"]).inc();
+        registry.register(Box::new(counter)).unwrap();
+        let total = IntCounter::new("requests_total", "Requests").unwrap();
+        total.inc_by(2);
+        registry.register(Box::new(total)).unwrap();
+        let gauge = IntGauge::new("lag", "Lag").unwrap();
+        gauge.set(3);
+        registry.register(Box::new(gauge)).unwrap();
+        registry.gather()
+    }
+
+    #[test]
+    fn prometheus_format() {
+        let format = MetricsFormat::from_accept(Some("text/plain"));
+        assert_eq!(format, MetricsFormat::Prometheus);
+        assert_eq!(MetricsFormat::from_accept(None), MetricsFormat::Prometheus);
+        assert!(format.content_type().starts_with("text/plain"));
+
+        let metrics = encode_metrics(&families(), format).unwrap();
+        assert!(metrics.contains("handled{status=\"success\"} 1\n"));
+        assert!(metrics.contains("\nrequests_total 2\n"));
+        assert!(!metrics.contains("# EOF"));
+
+        let format = MetricsFormat::from_accept(Some("application/openmetrics-text;q=0"));
+        assert_eq!(format, MetricsFormat::Prometheus);
+        let format =
+            MetricsFormat::from_accept(Some("application/openmetrics-text;q=0.3,text/plain;q=0.5"));
+        assert_eq!(format, MetricsFormat::Prometheus);
+    }
+
+    #[test]
+    fn open_metrics_format() {
+        let format = MetricsFormat::from_accept(Some(
+            "application/openmetrics-text;version=1.0.0,text/plain;q=0.5",
+        ));
+        assert_eq!(format, MetricsFormat::OpenMetrics);
+        assert!(format
+            .content_type()
+            .starts_with("application/openmetrics-text"));
+
+        let metrics = encode_metrics(&families(), format).unwrap();
+        assert!(metrics.contains("# TYPE handled counter\n"));
+        assert!(metrics.contains("handled_total{status=\"success\"} 1\n"));
+        // Counters already named with the suffix are not suffixed twice.
+        assert!(metrics.contains("# TYPE requests counter\n"));
+        assert!(metrics.contains("\nrequests_total 2\n"));
+        assert!(!metrics.contains("requests_total_total"));
+        assert!(metrics.contains("\nlag 3\n"));
+        assert!(metrics.ends_with("\n# EOF\n"));
+    }
 }
@@ -1,11 +1,20 @@
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::Error;
 
 pub enum InputEvent {
     MqaEvent(MqaEvent),
-    Unknown { namespace: String, name: String },
+    Unknown {
+        namespace: String,
+        name: String,
+    },
+    /// Message without payload, e.g. a tombstone.
+    Empty,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct MqaEvent {
     #[serde(rename = "type")]
     pub event_type: MqaEventType,
@@ -15,7 +24,69 @@ pub struct MqaEvent {
     pub timestamp: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl MqaEvent {
+    /// Builder of events, e.g. for tests producing events.
+    /// Defaults to a properties checked event for a random id, timestamped now.
+    pub fn builder() -> MqaEventBuilder {
+        MqaEventBuilder {
+            event_type: MqaEventType::PropertiesChecked,
+            fdk_id: Uuid::new_v4(),
+            graph: String::new(),
+            timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MqaEventBuilder {
+    event_type: MqaEventType,
+    fdk_id: Uuid,
+    graph: String,
+    timestamp: i64,
+}
+
+impl MqaEventBuilder {
+    pub fn event_type(mut self, event_type: MqaEventType) -> Self {
+        self.event_type = event_type;
+        self
+    }
+
+    pub fn fdk_id(mut self, fdk_id: Uuid) -> Self {
+        self.fdk_id = fdk_id;
+        self
+    }
+
+    pub fn graph(mut self, graph: impl Into<String>) -> Self {
+        self.graph = graph.into();
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Builds event, failing if it could not be produced or would not be handled.
+    pub fn build(self) -> Result<MqaEvent, Error> {
+        if self.event_type == MqaEventType::Unknown {
+            return Err("unable to build event of unknown type".into());
+        }
+        if self.graph.trim().is_empty() {
+            return Err("unable to build event without graph".into());
+        }
+        if self.timestamp < 0 {
+            return Err(format!("unable to build event with timestamp {}", self.timestamp).into());
+        }
+        Ok(MqaEvent {
+            event_type: self.event_type,
+            fdk_id: self.fdk_id.to_string(),
+            graph: self.graph,
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum MqaEventType {
     #[serde(rename = "PROPERTIES_CHECKED")]
     PropertiesChecked,
@@ -26,3 +97,139 @@ pub enum MqaEventType {
     #[serde(other)]
     Unknown,
 }
+
+/// Scores of a dataset, produced to the output topic when scores are sunk to kafka.
+/// Scores are encoded as the json posted to the scoring api.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoreEvent {
+    #[serde(rename = "fdkId")]
+    pub fdk_id: String,
+    pub scores: String,
+    pub timestamp: i64,
+}
+
+/// Event from legacy producers, carrying a graph equivalent to `MqaEvent`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LegacyDatasetEvent {
+    #[serde(rename = "type")]
+    pub event_type: LegacyDatasetEventType,
+    #[serde(rename = "fdkId")]
+    pub fdk_id: String,
+    pub graph: String,
+    pub timestamp: i64,
+}
+
+/// Legacy event types, of which only harvested datasets are scored.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum LegacyDatasetEventType {
+    #[serde(rename = "DATASET_HARVESTED")]
+    DatasetHarvested,
+    #[serde(other)]
+    Unknown,
+}
+
+impl From<LegacyDatasetEvent> for MqaEvent {
+    fn from(event: LegacyDatasetEvent) -> Self {
+        Self {
+            // Unknown legacy events, e.g. removals, are skipped as unknown mqa events.
+            event_type: match event.event_type {
+                LegacyDatasetEventType::DatasetHarvested => MqaEventType::PropertiesChecked,
+                LegacyDatasetEventType::Unknown => MqaEventType::Unknown,
+            },
+            fdk_id: event.fdk_id,
+            graph: event.graph,
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apache_avro::{from_avro_datum, from_value, to_avro_datum, to_value, Schema};
+
+    use super::*;
+    use crate::test::MEASUREMENT_GRAPH;
+
+    #[test]
+    fn built_event_round_trip() {
+        let fdk_id = Uuid::new_v4();
+        let event = MqaEvent::builder()
+            .event_type(MqaEventType::UrlsChecked)
+            .fdk_id(fdk_id)
+            .graph(MEASUREMENT_GRAPH)
+            .timestamp(1647698566000)
+            .build()
+            .unwrap();
+        assert_eq!(event.fdk_id, fdk_id.to_string());
+
+        let schema =
+            Schema::parse_str(include_str!("../kafka/schemas/no.fdk.mqa.MQAEvent.json")).unwrap();
+        let value = to_value(&event).unwrap().resolve(&schema).unwrap();
+        let datum = to_avro_datum(&schema, value).unwrap();
+        let decoded = from_avro_datum(&schema, &mut datum.as_slice(), None).unwrap();
+        assert_eq!(from_value::<MqaEvent>(&decoded).unwrap(), event);
+    }
+
+    #[test]
+    fn score_event_round_trip() {
+        let event = ScoreEvent {
+            fdk_id: Uuid::new_v4().to_string(),
+            scores: r#"{"dataset":{},"distributions":[]}"#.to_string(),
+            timestamp: 1647698566000,
+        };
+
+        let schema = Schema::parse_str(include_str!(
+            "../kafka/schemas/no.fdk.mqa.MQAScoreEvent.json"
+        ))
+        .unwrap();
+        let value = to_value(&event).unwrap().resolve(&schema).unwrap();
+        let datum = to_avro_datum(&schema, value).unwrap();
+        let decoded = from_avro_datum(&schema, &mut datum.as_slice(), None).unwrap();
+        assert_eq!(from_value::<ScoreEvent>(&decoded).unwrap(), event);
+    }
+
+    #[test]
+    fn builder_defaults() {
+        let before = Utc::now().timestamp_millis();
+        let event = MqaEvent::builder()
+            .graph(MEASUREMENT_GRAPH)
+            .build()
+            .unwrap();
+        assert_eq!(event.event_type, MqaEventType::PropertiesChecked);
+        assert!(Uuid::parse_str(&event.fdk_id).is_ok());
+        assert!(event.timestamp >= before);
+    }
+
+    #[test]
+    fn invalid_builder() {
+        assert!(MqaEvent::builder().build().is_err());
+        assert!(MqaEvent::builder()
+            .event_type(MqaEventType::Unknown)
+            .graph(MEASUREMENT_GRAPH)
+            .build()
+            .is_err());
+        assert!(MqaEvent::builder()
+            .graph(MEASUREMENT_GRAPH)
+            .timestamp(-1)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn legacy_event_types() {
+        let legacy_event = |event_type| LegacyDatasetEvent {
+            event_type,
+            fdk_id: "foo".to_string(),
+            graph: MEASUREMENT_GRAPH.to_string(),
+            timestamp: 1647698566000,
+        };
+        assert_eq!(
+            MqaEvent::from(legacy_event(LegacyDatasetEventType::DatasetHarvested)).event_type,
+            MqaEventType::PropertiesChecked
+        );
+        assert_eq!(
+            MqaEvent::from(legacy_event(LegacyDatasetEventType::Unknown)).event_type,
+            MqaEventType::Unknown
+        );
+    }
+}
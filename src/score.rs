@@ -1,19 +1,94 @@
-use oxigraph::model::{NamedNode, NamedNodeRef};
-use std::collections::HashMap;
+use oxigraph::model::{NamedNode, NamedNodeRef, NamedOrBlankNode};
+use std::collections::{BTreeSet, HashMap};
 
 use crate::{
     assessment_graph::AssessmentGraph,
     error::Error,
     measurement_value::MeasurementValue,
+    metrics::inc_unknown_metric,
     score_graph::{ScoreDefinitions, ScoreDimension},
 };
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Score {
+    pub kind: ScoreKind,
     pub assessment: NamedNode,
-    pub resource: NamedNode,
+    pub resource: NamedOrBlankNode,
     pub dimensions: Vec<DimensionScore>,
     pub score: u64,
+    /// Assessment of the distribution merged into a dataset score, if any.
+    pub best_distribution: Option<NamedNode>,
+}
+
+impl Score {
+    /// IRI of scored resource. Blank node resources get a stable IRI derived from the assessment.
+    pub fn resource_iri(&self) -> String {
+        resource_iri(&self.assessment, &self.resource)
+    }
+
+    /// Score with metrics without measurements scored according to `policy`.
+    /// Total and dimension scores are unaffected, as unscored metrics count as 0.
+    pub fn with_missing_measurements(self, policy: MissingMeasurements) -> Score {
+        let dimensions = match policy {
+            MissingMeasurements::Unscored => self.dimensions,
+            MissingMeasurements::Zero => self
+                .dimensions
+                .into_iter()
+                .map(|dimension| DimensionScore {
+                    metrics: dimension
+                        .metrics
+                        .into_iter()
+                        .map(|metric| MetricScore {
+                            score: metric.score.or(Some(0)),
+                            ..metric
+                        })
+                        .collect(),
+                    ..dimension
+                })
+                .collect(),
+        };
+        Score { dimensions, ..self }
+    }
+}
+
+/// IRI of resource of an assessment, derived from the assessment if the resource is a blank node.
+pub fn resource_iri(assessment: &NamedNode, resource: &NamedOrBlankNode) -> String {
+    match resource {
+        NamedOrBlankNode::NamedNode(node) => node.as_str().to_string(),
+        NamedOrBlankNode::BlankNode(_) => format!("{}#resource", assessment.as_str()),
+    }
+}
+
+/// How metrics without measurements are scored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MissingMeasurements {
+    /// Left unscored, distinguishable from measured metrics scoring 0.
+    #[default]
+    Unscored,
+    /// Scored as 0, same as measured metrics that failed.
+    Zero,
+}
+
+impl TryFrom<&str> for MissingMeasurements {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "unscored" => Ok(Self::Unscored),
+            "zero" => Ok(Self::Zero),
+            _ => Err(format!(
+                "invalid missing measurements policy '{value}', expected 'unscored' or 'zero'"
+            )
+            .into()),
+        }
+    }
+}
+
+/// Kind of scored resource.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScoreKind {
+    Dataset,
+    Distribution,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -27,6 +102,8 @@ pub struct DimensionScore {
 pub struct MetricScore {
     pub id: NamedNode,
     pub score: Option<u64>,
+    /// Resource the scored measurement was computed on, if given by the measurement.
+    pub computed_on: Option<NamedOrBlankNode>,
 }
 
 fn sum_dimensions(dimensions: &Vec<DimensionScore>) -> u64 {
@@ -40,12 +117,55 @@ fn sum_metrics(metrics: &Vec<MetricScore>) -> u64 {
         .sum()
 }
 
+/// How distribution scores are aggregated into the dataset score.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Aggregation {
+    /// Dataset measurements merged with the best scoring distribution.
+    #[default]
+    BestDistribution,
+    /// Dataset measurements merged with the max of each metric across all distributions.
+    UnionMax,
+    /// Dataset measurements only, distributions are ignored and left unscored.
+    DatasetOnly,
+}
+
+impl TryFrom<&str> for Aggregation {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "best-distribution" => Ok(Self::BestDistribution),
+            "union-max" => Ok(Self::UnionMax),
+            "dataset-only" => Ok(Self::DatasetOnly),
+            _ => Err(format!(
+                "invalid AGGREGATION '{value}', expected 'best-distribution', 'union-max' or 'dataset-only'"
+            )
+            .into()),
+        }
+    }
+}
+
 /// Calculates score for all metrics in all dimensions, for all distributions.
 pub fn calculate_score(
     measurement_graph: &AssessmentGraph,
     score_definitions: &ScoreDefinitions,
 ) -> Result<(Score, Vec<Score>), Error> {
-    let graph_measurements = measurement_graph.quality_measurements()?;
+    calculate_score_with(measurement_graph, score_definitions, Aggregation::default())
+}
+
+/// Calculates score for all metrics in all dimensions, for all distributions,
+/// aggregating the dataset score according to `aggregation`.
+/// No distribution scores are returned for `Aggregation::DatasetOnly`.
+pub fn calculate_score_with(
+    measurement_graph: &AssessmentGraph,
+    score_definitions: &ScoreDefinitions,
+    aggregation: Aggregation,
+) -> Result<(Score, Vec<Score>), Error> {
+    let quality_measurements = measurement_graph.quality_measurements()?;
+    count_unknown_metrics(&quality_measurements, score_definitions);
+    let measurement_resources = measurement_graph.measurement_resources()?;
+    let graph_measurements =
+        InternedMeasurements::new(&quality_measurements).with_resources(&measurement_resources);
 
     let dataset = measurement_graph.dataset()?;
     let dataset_dimensions = node_dimension_scores(
@@ -54,7 +174,10 @@ pub fn calculate_score(
         dataset.assessment.as_ref(),
     )?;
 
-    let distributions = measurement_graph.distributions()?;
+    let distributions = match aggregation {
+        Aggregation::DatasetOnly => Vec::new(),
+        _ => measurement_graph.distributions()?,
+    };
     let distribution_scores: Vec<Score> = distributions
         .into_iter()
         .map(|distribution| {
@@ -64,71 +187,199 @@ pub fn calculate_score(
                 distribution.assessment.as_ref(),
             )?;
             Ok(Score {
+                kind: ScoreKind::Distribution,
                 assessment: distribution.assessment.clone(),
                 resource: distribution.resource.clone(),
                 score: sum_dimensions(&dimensions),
                 dimensions,
+                best_distribution: None,
             })
         })
         .collect::<Result<_, Error>>()?;
 
-    let dataset_merged_distribution_scores: Vec<Score> = distribution_scores
-        .iter()
-        .map(|score| {
-            let dimensions = merge_dimension_scores(score.dimensions.clone(), &dataset_dimensions);
-            Score {
-                assessment: score.assessment.clone(),
-                resource: score.resource.clone(),
-                score: sum_dimensions(&dimensions),
-                dimensions,
-            }
-        })
-        .collect();
-
-    let (dataset_total_score, dataset_dimensions) =
-        if let Some(best) = best_score(dataset_merged_distribution_scores) {
-            (best.score, best.dimensions)
-        } else {
-            (sum_dimensions(&dataset_dimensions), dataset_dimensions)
-        };
+    let (dataset_dimensions, best_distribution) = match aggregation {
+        Aggregation::BestDistribution => distribution_scores
+            .iter()
+            .map(|score| {
+                (
+                    merge_dimension_scores(score.dimensions.clone(), &dataset_dimensions),
+                    Some(score.assessment.clone()),
+                )
+            })
+            .max_by_key(|(dimensions, _)| sum_dimensions(dimensions))
+            .unwrap_or((dataset_dimensions, None)),
+        Aggregation::UnionMax => (
+            distribution_scores
+                .iter()
+                .fold(dataset_dimensions, |merged, score| {
+                    merge_dimension_scores(merged, &score.dimensions)
+                }),
+            None,
+        ),
+        Aggregation::DatasetOnly => (dataset_dimensions, None),
+    };
+    let dataset_total_score = sum_dimensions(&dataset_dimensions);
 
     Ok((
         Score {
+            kind: ScoreKind::Dataset,
             assessment: dataset.assessment,
             resource: dataset.resource,
             dimensions: dataset_dimensions,
             score: dataset_total_score,
+            best_distribution,
         },
         distribution_scores,
     ))
 }
 
-// Merges two node scores by taking the max value of each metric.
-// NOTE: both inputs MUST be of same size have equal dimension/metric order.
+/// Calculates score for all metrics in all dimensions, for dataset only.
+/// Distributions are ignored, and an empty list of distribution scores is returned.
+pub fn calculate_dataset_score(
+    measurement_graph: &AssessmentGraph,
+    score_definitions: &ScoreDefinitions,
+) -> Result<(Score, Vec<Score>), Error> {
+    calculate_score_with(
+        measurement_graph,
+        score_definitions,
+        Aggregation::DatasetOnly,
+    )
+}
+
+/// Metrics measured in graph that have no score definition, sorted by IRI.
+/// Useful to check that deployed score values cover all metrics emitted by producers.
+pub fn unscored_metrics(
+    score_definitions: &ScoreDefinitions,
+    graph: &AssessmentGraph,
+) -> Result<Vec<NamedNode>, Error> {
+    Ok(
+        unknown_metrics(&graph.quality_measurements()?, score_definitions)
+            .into_iter()
+            .cloned()
+            .collect(),
+    )
+}
+
+fn unknown_metrics<'a>(
+    measurements: &'a HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>,
+    score_definitions: &ScoreDefinitions,
+) -> BTreeSet<&'a NamedNode> {
+    let defined = score_definitions.metric_ids();
+    measurements
+        .keys()
+        .map(|(_, metric)| metric)
+        .filter(|metric| !defined.contains(metric))
+        .collect()
+}
+
+/// Counts measured metrics that have no score definition, and would silently go unscored.
+fn count_unknown_metrics(
+    measurements: &HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>,
+    score_definitions: &ScoreDefinitions,
+) {
+    for metric in unknown_metrics(measurements, score_definitions) {
+        tracing::debug!(
+            metric = metric.as_str(),
+            "measured metric has no score definition"
+        );
+        inc_unknown_metric(metric.as_str());
+    }
+}
+
+// Merges two node scores by taking the max value of each metric, aligned by dimension
+// and metric IRI. Dimensions and metrics only present in `other` are appended.
 fn merge_dimension_scores(
     dimensions: Vec<DimensionScore>,
     other: &Vec<DimensionScore>,
 ) -> Vec<DimensionScore> {
-    dimensions
+    let mut merged: Vec<DimensionScore> = dimensions
         .into_iter()
-        .zip(other)
-        .map(|(dimension, other)| {
-            let metrics = dimension
+        .map(|dimension| {
+            let other_metrics = other
+                .iter()
+                .find(|other| other.id == dimension.id)
+                .map(|other| other.metrics.as_slice())
+                .unwrap_or_default();
+            let mut metrics: Vec<MetricScore> = dimension
                 .metrics
                 .into_iter()
-                .zip(other.metrics.iter())
-                .map(|(metric, other)| MetricScore {
-                    id: metric.id,
-                    score: metric.score.max(other.score.clone()),
+                .map(|metric| {
+                    // The higher score is kept along with the resource it was computed on.
+                    match other_metrics.iter().find(|other| other.id == metric.id) {
+                        Some(other) if other.score > metric.score => other.clone(),
+                        _ => metric,
+                    }
                 })
                 .collect();
+            for other in other_metrics {
+                if !metrics.iter().any(|metric| metric.id == other.id) {
+                    metrics.push(other.clone());
+                }
+            }
             DimensionScore {
                 id: dimension.id,
                 score: sum_metrics(&metrics),
                 metrics,
             }
         })
-        .collect()
+        .collect();
+    for other in other {
+        if !merged.iter().any(|dimension| dimension.id == other.id) {
+            merged.push(other.clone());
+        }
+    }
+    merged
+}
+
+/// Counts scored metrics of a score, and metrics defined in total: (scored, total).
+pub fn coverage(score: &Score, score_definitions: &ScoreDefinitions) -> (u64, u64) {
+    let scored = score
+        .dimensions
+        .iter()
+        .flat_map(|dimension| dimension.metrics.iter())
+        .filter(|metric| metric.score.is_some())
+        .count();
+    let total = score_definitions
+        .dimensions
+        .iter()
+        .map(|dimension| dimension.metrics.len())
+        .sum::<usize>();
+    (scored as u64, total as u64)
+}
+
+/// Metric with the largest gap to its max score in each dimension, as
+/// (dimension, metric, gap), sorted by gap descending. Dimensions at max score are left out.
+pub fn improvement_opportunities(
+    score: &Score,
+    score_definitions: &ScoreDefinitions,
+) -> Vec<(NamedNode, NamedNode, u64)> {
+    let mut opportunities: Vec<(NamedNode, NamedNode, u64)> = score_definitions
+        .dimensions
+        .iter()
+        .filter_map(|definition| {
+            let dimension = score
+                .dimensions
+                .iter()
+                .find(|dimension| dimension.id == definition.id);
+            definition
+                .metrics
+                .iter()
+                .map(|metric| {
+                    let scored = dimension
+                        .and_then(|dimension| {
+                            dimension.metrics.iter().find(|other| other.id == metric.id)
+                        })
+                        .and_then(|other| other.score)
+                        .unwrap_or_default();
+                    (metric, metric.score.saturating_sub(scored))
+                })
+                .filter(|(_, gap)| *gap > 0)
+                .max_by_key(|(_, gap)| *gap)
+                .map(|(metric, gap)| (definition.id.clone(), metric.id.clone(), gap))
+        })
+        .collect();
+    opportunities.sort_by_key(|(_, _, gap)| std::cmp::Reverse(*gap));
+    opportunities
 }
 
 // Find best scoring distribution.
@@ -136,12 +387,78 @@ pub fn best_score(scores: Vec<Score>) -> Option<Score> {
     scores.into_iter().max_by_key::<u64, _>(|score| score.score)
 }
 
+// Find best scoring distribution, without taking ownership of scores.
+pub fn best_score_ref(scores: &[Score]) -> Option<&Score> {
+    scores.iter().max_by_key::<u64, _>(|score| score.score)
+}
+
+/// Quality measurements keyed by interned (node, metric) handles, so that lookups
+/// don't allocate IRIs.
+struct InternedMeasurements<'a> {
+    handles: HashMap<&'a str, u32>,
+    values: HashMap<(u32, u32), &'a Vec<MeasurementValue>>,
+    resources: HashMap<(u32, u32), &'a NamedOrBlankNode>,
+}
+
+impl<'a> InternedMeasurements<'a> {
+    fn new(measurements: &'a HashMap<(NamedNode, NamedNode), Vec<MeasurementValue>>) -> Self {
+        let mut handles = HashMap::new();
+        let mut intern = |iri: &'a str| {
+            let next = handles.len() as u32;
+            *handles.entry(iri).or_insert(next)
+        };
+        let values = measurements
+            .iter()
+            .map(|((node, metric), values)| {
+                ((intern(node.as_str()), intern(metric.as_str())), values)
+            })
+            .collect();
+        Self {
+            handles,
+            values,
+            resources: HashMap::new(),
+        }
+    }
+
+    /// Adds resources that measurements are computed on, as map: (node, metric) -> resource.
+    /// Resources of nodes or metrics without measurements are ignored.
+    fn with_resources(
+        mut self,
+        resources: &'a HashMap<(NamedNode, NamedNode), NamedOrBlankNode>,
+    ) -> Self {
+        self.resources = resources
+            .iter()
+            .filter_map(|((node, metric), resource)| {
+                let key = (self.handle(node.as_str())?, self.handle(metric.as_str())?);
+                Some((key, resource))
+            })
+            .collect();
+        self
+    }
+
+    /// Interned handle of an IRI, if present in any measurement.
+    fn handle(&self, iri: &str) -> Option<u32> {
+        self.handles.get(iri).cloned()
+    }
+
+    /// Measurement values of a metric, for an interned node.
+    fn get(&self, node: u32, metric: &str) -> Option<&'a Vec<MeasurementValue>> {
+        self.values.get(&(node, self.handle(metric)?)).cloned()
+    }
+
+    /// Resource that a metric was measured on, for an interned node.
+    fn resource(&self, node: u32, metric: &str) -> Option<&'a NamedOrBlankNode> {
+        self.resources.get(&(node, self.handle(metric)?)).cloned()
+    }
+}
+
 /// Calculates score for all metrics in all dimensions, for a distribution or dataset node.
 fn node_dimension_scores(
     score_definitions: &ScoreDefinitions,
-    graph_measurements: &HashMap<(NamedNode, NamedNode), MeasurementValue>,
+    graph_measurements: &InternedMeasurements,
     node: NamedNodeRef,
 ) -> Result<Vec<DimensionScore>, Error> {
+    let node = graph_measurements.handle(node.as_str());
     score_definitions
         .dimensions
         .iter()
@@ -149,12 +466,20 @@ fn node_dimension_scores(
             let metrics = metrics
                 .iter()
                 .map(|metric| {
+                    let values =
+                        node.and_then(|node| graph_measurements.get(node, metric.id.as_str()));
                     Ok(MetricScore {
                         id: metric.id.clone(),
-                        score: match graph_measurements.get(&(node.into(), metric.id.clone())) {
-                            Some(val) => Some(metric.score(val)?),
+                        score: match values {
+                            Some(values) => Some(metric.score(values)?),
                             None => None,
                         },
+                        computed_on: match (node, values) {
+                            (Some(node), Some(_)) => graph_measurements
+                                .resource(node, metric.id.as_str())
+                                .cloned(),
+                            _ => None,
+                        },
                     })
                 })
                 .collect::<Result<_, Error>>()?;
@@ -171,14 +496,15 @@ fn node_dimension_scores(
 mod tests {
     use super::*;
     use crate::{
-        helpers::parse_graphs,
+        metrics::UNKNOWN_METRICS,
         score_graph::ScoreGraph,
         test::{mqa_node, node, MEASUREMENT_GRAPH, METRIC_GRAPH, SCORE_GRAPH},
     };
 
     #[test]
     fn score_measurements() {
-        let score_definitions = ScoreGraph(parse_graphs(vec![METRIC_GRAPH, SCORE_GRAPH]).unwrap())
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
             .scores()
             .unwrap();
 
@@ -190,19 +516,22 @@ mod tests {
         assert_eq!(
             dataset_score,
             Score {
+                kind: ScoreKind::Dataset,
                 assessment: node("https://dataset.assessment.foo"),
-                resource: node("https://dataset.foo"),
+                resource: node("https://dataset.foo").into(),
                 dimensions: vec![
                     DimensionScore {
                         id: mqa_node("accessibility"),
                         metrics: vec![
                             MetricScore {
                                 id: mqa_node("accessUrlStatusCode"),
-                                score: Some(50)
+                                score: Some(50),
+                                computed_on: None,
                             },
                             MetricScore {
                                 id: mqa_node("downloadUrlAvailability"),
                                 score: Some(20),
+                                computed_on: None,
                             },
                         ],
                         score: 70,
@@ -211,18 +540,21 @@ mod tests {
                         id: mqa_node("interoperability"),
                         metrics: vec![MetricScore {
                             id: mqa_node("formatAvailability"),
-                            score: Some(0)
+                            score: Some(0),
+                            computed_on: None,
                         }],
                         score: 0
                     },
                 ],
                 score: 70,
+                best_distribution: Some(node("https://distribution.assessment.a")),
             }
         );
 
         let a = Score {
+            kind: ScoreKind::Distribution,
             assessment: node("https://distribution.assessment.a"),
-            resource: node("https://distribution.a"),
+            resource: node("https://distribution.a").into(),
             dimensions: vec![
                 DimensionScore {
                     id: mqa_node("accessibility"),
@@ -230,10 +562,12 @@ mod tests {
                         MetricScore {
                             id: mqa_node("accessUrlStatusCode"),
                             score: Some(50),
+                            computed_on: None,
                         },
                         MetricScore {
                             id: mqa_node("downloadUrlAvailability"),
                             score: None,
+                            computed_on: None,
                         },
                     ],
                     score: 50,
@@ -243,15 +577,18 @@ mod tests {
                     metrics: vec![MetricScore {
                         id: mqa_node("formatAvailability"),
                         score: Some(0),
+                        computed_on: None,
                     }],
                     score: 0,
                 },
             ],
             score: 50,
+            best_distribution: None,
         };
         let b = Score {
+            kind: ScoreKind::Distribution,
             assessment: node("https://distribution.assessment.b"),
-            resource: node("https://distribution.b"),
+            resource: node("https://distribution.b").into(),
             dimensions: vec![
                 DimensionScore {
                     id: mqa_node("accessibility"),
@@ -259,10 +596,12 @@ mod tests {
                         MetricScore {
                             id: mqa_node("accessUrlStatusCode"),
                             score: None,
+                            computed_on: None,
                         },
                         MetricScore {
                             id: mqa_node("downloadUrlAvailability"),
                             score: None,
+                            computed_on: None,
                         },
                     ],
                     score: 0,
@@ -272,13 +611,459 @@ mod tests {
                     metrics: vec![MetricScore {
                         id: mqa_node("formatAvailability"),
                         score: Some(20),
+                        computed_on: None,
                     }],
                     score: 20,
                 },
             ],
             score: 20,
+            best_distribution: None,
         };
-        assert_eq!(distribution_scores, vec![b.clone(), a.clone()]);
+        assert_eq!(distribution_scores, vec![a.clone(), b.clone()]);
+        assert_eq!(best_score_ref(&distribution_scores), Some(&a));
         assert_eq!(best_score(distribution_scores), Some(a));
     }
+
+    #[test]
+    fn blank_node_resource() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph
+            .load(MEASUREMENT_GRAPH.replace("<https://distribution.a> .", "_:resource ."))
+            .unwrap();
+        let (_, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        let a = distribution_scores
+            .iter()
+            .find(|score| score.assessment == node("https://distribution.assessment.a"))
+            .unwrap();
+        assert!(matches!(a.resource, NamedOrBlankNode::BlankNode(_)));
+        assert_eq!(a.score, 50);
+        assert_eq!(
+            a.resource_iri(),
+            "https://distribution.assessment.a#resource"
+        );
+        measurement_graph
+            .insert_scores(&distribution_scores)
+            .unwrap();
+    }
+
+    #[test]
+    fn score_kinds() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(dataset_score.kind, ScoreKind::Dataset);
+        assert_eq!(distribution_scores.len(), 2);
+        assert!(distribution_scores
+            .iter()
+            .all(|score| score.kind == ScoreKind::Distribution));
+    }
+
+    #[test]
+    fn dataset_only() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_dataset_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(distribution_scores, vec![]);
+        assert_eq!(
+            dataset_score,
+            Score {
+                kind: ScoreKind::Dataset,
+                assessment: node("https://dataset.assessment.foo"),
+                resource: node("https://dataset.foo").into(),
+                dimensions: vec![
+                    dimension(
+                        "accessibility",
+                        vec![
+                            metric("accessUrlStatusCode", None),
+                            metric("downloadUrlAvailability", Some(20)),
+                        ],
+                    ),
+                    dimension("interoperability", vec![metric("formatAvailability", None)]),
+                ],
+                score: 20,
+                best_distribution: None,
+            }
+        );
+    }
+
+    #[test]
+    fn improvements() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(
+            improvement_opportunities(&dataset_score, &score_definitions),
+            vec![(
+                mqa_node("interoperability"),
+                mqa_node("formatAvailability"),
+                20
+            )]
+        );
+
+        let b = distribution_scores
+            .iter()
+            .find(|score| score.assessment == node("https://distribution.assessment.b"))
+            .unwrap();
+        assert_eq!(
+            improvement_opportunities(b, &score_definitions),
+            vec![(
+                mqa_node("accessibility"),
+                mqa_node("accessUrlStatusCode"),
+                50
+            )]
+        );
+    }
+
+    #[test]
+    fn measurement_resources() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph
+            .load(format!(
+                r#"{MEASUREMENT_GRAPH}
+                _:b <http://www.w3.org/ns/dqv#computedOn> <https://distribution.a> .
+                "#
+            ))
+            .unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        let a = distribution_scores
+            .iter()
+            .find(|score| score.assessment == node("https://distribution.assessment.a"))
+            .unwrap();
+        let access_url = &a.dimensions[0].metrics[0];
+        assert_eq!(access_url.id, mqa_node("accessUrlStatusCode"));
+        assert_eq!(access_url.score, Some(50));
+        assert_eq!(
+            access_url.computed_on,
+            Some(node("https://distribution.a").into())
+        );
+        // Measurements without a computed-on resource have none.
+        assert_eq!(a.dimensions[1].metrics[0].computed_on, None);
+
+        // Dataset scores merged with the best distribution keep its resource.
+        assert_eq!(
+            dataset_score.dimensions[0].metrics[0].computed_on,
+            Some(node("https://distribution.a").into())
+        );
+        assert_eq!(dataset_score.dimensions[0].metrics[1].computed_on, None);
+    }
+
+    #[test]
+    fn aggregation_strategies() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let dataset_score = |aggregation| {
+            let (dataset_score, distribution_scores) =
+                calculate_score_with(&measurement_graph, &score_definitions, aggregation).unwrap();
+            let distribution_count = match aggregation {
+                Aggregation::DatasetOnly => 0,
+                _ => 2,
+            };
+            assert_eq!(distribution_scores.len(), distribution_count);
+            dataset_score
+        };
+
+        let best = dataset_score(Aggregation::BestDistribution);
+        assert_eq!(best.score, 70);
+        assert_eq!(
+            best.best_distribution,
+            Some(node("https://distribution.assessment.a"))
+        );
+        assert_eq!(
+            best,
+            calculate_score(&measurement_graph, &score_definitions)
+                .unwrap()
+                .0
+        );
+
+        let union = dataset_score(Aggregation::UnionMax);
+        assert_eq!(union.best_distribution, None);
+        assert_eq!(
+            union.dimensions,
+            vec![
+                dimension(
+                    "accessibility",
+                    vec![
+                        metric("accessUrlStatusCode", Some(50)),
+                        metric("downloadUrlAvailability", Some(20)),
+                    ],
+                ),
+                dimension(
+                    "interoperability",
+                    vec![metric("formatAvailability", Some(20))]
+                ),
+            ]
+        );
+        assert_eq!(union.score, 90);
+
+        let dataset_only = dataset_score(Aggregation::DatasetOnly);
+        assert_eq!(
+            dataset_only,
+            calculate_dataset_score(&measurement_graph, &score_definitions)
+                .unwrap()
+                .0
+        );
+        assert_eq!(dataset_only.score, 20);
+    }
+
+    #[test]
+    fn missing_measurements_policy() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (_, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+        let b = distribution_scores
+            .into_iter()
+            .find(|score| score.assessment == node("https://distribution.assessment.b"))
+            .unwrap();
+
+        let unscored = b
+            .clone()
+            .with_missing_measurements(MissingMeasurements::Unscored);
+        assert_eq!(unscored, b);
+        assert_eq!(
+            unscored.dimensions[0].metrics,
+            vec![
+                metric("accessUrlStatusCode", None),
+                metric("downloadUrlAvailability", None),
+            ]
+        );
+
+        let zero = b
+            .clone()
+            .with_missing_measurements(MissingMeasurements::Zero);
+        assert_eq!(
+            zero.dimensions,
+            vec![
+                dimension(
+                    "accessibility",
+                    vec![
+                        metric("accessUrlStatusCode", Some(0)),
+                        metric("downloadUrlAvailability", Some(0)),
+                    ],
+                ),
+                dimension(
+                    "interoperability",
+                    vec![metric("formatAvailability", Some(20))]
+                ),
+            ]
+        );
+        assert_eq!(zero.score, b.score);
+
+        assert_eq!(
+            MissingMeasurements::try_from(" Zero").unwrap(),
+            MissingMeasurements::Zero
+        );
+        assert!(MissingMeasurements::try_from("none").is_err());
+    }
+
+    #[test]
+    fn no_measurements() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
+                "#,
+            )
+            .unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(dataset_score.score, 0);
+        assert!(distribution_scores.is_empty());
+        assert_eq!(coverage(&dataset_score, &score_definitions), (0, 3));
+    }
+
+    #[test]
+    fn metric_coverage() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let (dataset_score, distribution_scores) =
+            calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(coverage(&dataset_score, &score_definitions), (3, 3));
+        let a = distribution_scores
+            .iter()
+            .find(|score| score.assessment == node("https://distribution.assessment.a"))
+            .unwrap();
+        assert_eq!(coverage(a, &score_definitions), (2, 3));
+    }
+
+    fn metric(name: &str, score: Option<u64>) -> MetricScore {
+        MetricScore {
+            id: mqa_node(name),
+            score,
+            computed_on: None,
+        }
+    }
+
+    fn dimension(name: &str, metrics: Vec<MetricScore>) -> DimensionScore {
+        DimensionScore {
+            id: mqa_node(name),
+            score: sum_metrics(&metrics),
+            metrics,
+        }
+    }
+
+    #[test]
+    fn merge_differing_metrics() {
+        let distribution = vec![dimension(
+            "accessibility",
+            vec![metric("accessUrlStatusCode", Some(50))],
+        )];
+        let dataset = vec![
+            dimension(
+                "accessibility",
+                vec![
+                    metric("downloadUrlAvailability", Some(20)),
+                    metric("accessUrlStatusCode", Some(0)),
+                ],
+            ),
+            dimension("reusability", vec![metric("licenseAvailability", Some(20))]),
+        ];
+
+        assert_eq!(
+            merge_dimension_scores(distribution, &dataset),
+            vec![
+                dimension(
+                    "accessibility",
+                    vec![
+                        metric("accessUrlStatusCode", Some(50)),
+                        metric("downloadUrlAvailability", Some(20)),
+                    ],
+                ),
+                dimension("reusability", vec![metric("licenseAvailability", Some(20))]),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_unscored_metrics() {
+        let distribution = vec![dimension(
+            "interoperability",
+            vec![
+                metric("formatAvailability", None),
+                metric("mediaTypeAvailability", Some(10)),
+            ],
+        )];
+        let dataset = vec![dimension(
+            "interoperability",
+            vec![metric("formatAvailability", Some(20))],
+        )];
+
+        let merged = merge_dimension_scores(distribution, &dataset);
+        assert_eq!(merged[0].score, 30);
+        assert_eq!(merged[0].metrics[0], metric("formatAvailability", Some(20)));
+    }
+
+    #[test]
+    fn unknown_metric() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let metric = "https://data.norge.no/vocabulary/dcatno-mqa#undefinedAvailability";
+        let before = UNKNOWN_METRICS.with_label_values(&[metric]).get();
+
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        measurement_graph
+            .load(format!(
+                r#"
+                <https://distribution.assessment.a> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:x .
+                _:x <http://www.w3.org/ns/dqv#isMeasurementOf> <{metric}> .
+                _:x <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#
+            ))
+            .unwrap();
+        calculate_score(&measurement_graph, &score_definitions).unwrap();
+
+        assert_eq!(
+            UNKNOWN_METRICS.with_label_values(&[metric]).get(),
+            before + 1
+        );
+    }
+
+    #[test]
+    fn unscored() {
+        let score_definitions = ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        assert!(unscored_metrics(&score_definitions, &measurement_graph)
+            .unwrap()
+            .is_empty());
+
+        measurement_graph
+            .load(
+                r#"
+                <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:x .
+                _:x <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#undefinedAvailability> .
+                _:x <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            unscored_metrics(&score_definitions, &measurement_graph).unwrap(),
+            vec![mqa_node("undefinedAvailability")]
+        );
+    }
 }
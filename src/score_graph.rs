@@ -1,9 +1,14 @@
+use std::{collections::HashSet, fs, sync::Arc};
+
+use arc_swap::ArcSwap;
+use chrono::{TimeDelta, Utc};
+use lazy_static::lazy_static;
 use oxigraph::model::{vocab::rdf, NamedNode, NamedNodeRef, Term};
 
 use crate::{
     error::Error,
     helpers::execute_query,
-    helpers::{named_quad_subject, parse_graphs},
+    helpers::{named_quad_object, named_quad_subject, parse_graphs, sparql_iri},
     measurement_value::MeasurementValue,
     vocab::{dcat_mqa, dqv},
 };
@@ -12,8 +17,17 @@ pub static VOCAB_GRAPH: &str = include_str!("../graphs/dcatno-mqa-vocabulary.ttl
 pub static SCORE_GRAPH: &str =
     include_str!("../graphs/dcatno-mqa-vocabulary-default-score-values.ttl");
 
+lazy_static! {
+    static ref DEFAULT_SCORE_DEFINITIONS: ScoreDefinitions = ScoreGraph::new()
+        .and_then(|score_graph| score_graph.scores())
+        .expect("invalid bundled score graphs");
+}
+
 pub struct ScoreGraph(pub oxigraph::store::Store);
 
+/// Score definitions shared between workers, swappable at runtime.
+pub type SharedScoreDefinitions = Arc<ArcSwap<ScoreDefinitions>>;
+
 #[derive(Debug, PartialEq)]
 pub struct ScoreDefinitions {
     pub dimensions: Vec<ScoreDimension>,
@@ -31,12 +45,92 @@ pub struct ScoreDimension {
 pub struct ScoreMetric {
     pub id: NamedNode,
     pub score: u64,
+    pub reduction: MeasurementReduction,
+    /// How old a dateTime measurement may be and still pass.
+    pub freshness_window: TimeDelta,
+}
+
+/// Freshness window of metrics without `dcatno-mqa:freshnessWindowDays` in the score graph.
+pub const DEFAULT_FRESHNESS_WINDOW_DAYS: i64 = 30;
+
+/// How multiple measurements of the same metric, for the same node, are scored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MeasurementReduction {
+    /// Full score if any measurement passes.
+    #[default]
+    AnyTrue,
+    /// Scores only the best measurement value, e.g. the latest timestamp or a successful
+    /// status code.
+    Max,
+    /// Score proportional to the share of passing measurements.
+    Count,
+}
+
+impl TryFrom<&str> for MeasurementReduction {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "anyTrue" => Ok(Self::AnyTrue),
+            "max" => Ok(Self::Max),
+            "count" => Ok(Self::Count),
+            _ => Err(format!("unknown measurement reduction: '{value}'").into()),
+        }
+    }
+}
+
+impl ScoreMetric {
+    /// Metric with the default reduction and freshness window.
+    pub fn new(id: NamedNode, score: u64) -> Self {
+        Self {
+            id,
+            score,
+            reduction: MeasurementReduction::default(),
+            freshness_window: TimeDelta::days(DEFAULT_FRESHNESS_WINDOW_DAYS),
+        }
+    }
+}
+
+impl ScoreDefinitions {
+    /// IRIs of all defined metrics, across dimensions.
+    pub fn metric_ids(&self) -> HashSet<&NamedNode> {
+        self.dimensions
+            .iter()
+            .flat_map(|dimension| dimension.metrics.iter().map(|metric| &metric.id))
+            .collect()
+    }
+
+    /// Dimension that a metric is defined in.
+    pub fn dimension_of(&self, metric: &NamedNode) -> Option<&NamedNode> {
+        self.dimensions
+            .iter()
+            .find(|dimension| dimension.metrics.iter().any(|m| &m.id == metric))
+            .map(|dimension| &dimension.id)
+    }
 }
 
 impl ScoreGraph {
     // Loads score graph from files.
     pub fn new() -> Result<Self, Error> {
-        parse_graphs(vec![VOCAB_GRAPH, SCORE_GRAPH]).map(|store| Self(store))
+        Self::from_turtle(VOCAB_GRAPH, SCORE_GRAPH)
+    }
+
+    /// Loads score graph from a vocabulary and score values, e.g. historical definitions.
+    /// Fails if the vocabulary defines no dimensions to score, or a dimension
+    /// whose total score has no scoring metric to be inserted as.
+    pub fn from_turtle(vocab: &str, scores: &str) -> Result<Self, Error> {
+        let score_graph = Self(parse_graphs(vec![vocab, scores])?);
+        let dimensions = score_graph.dimensions()?;
+        if dimensions.is_empty() {
+            return Err("score graph has no dimensions".into());
+        }
+        if let Some(dimension) = dimensions
+            .iter()
+            .find(|dimension| dcat_mqa::dimension_scoring(dimension.as_ref()).is_none())
+        {
+            return Err(format!("no scoring metric defined for dimension '{dimension}'").into());
+        }
+        Ok(score_graph)
     }
 
     // Retrieves the metrics and values of each score dimension.
@@ -54,6 +148,23 @@ impl ScoreGraph {
                 })
             })
             .collect::<Result<Vec<ScoreDimension>, Error>>()?;
+
+        // A metric with multiple true scores, e.g. from an override graph merged on top of the
+        // bundled one, would otherwise be scored once per true score.
+        let mut metric_ids = HashSet::new();
+        for metric in dimensions
+            .iter()
+            .flat_map(|dimension| dimension.metrics.iter())
+        {
+            if !metric_ids.insert(&metric.id) {
+                return Err(format!(
+                    "duplicate metric '{}' in score graph, e.g. with multiple true scores",
+                    metric.id
+                )
+                .into());
+            }
+        }
+
         Ok(ScoreDefinitions {
             total_score: dimensions
                 .iter()
@@ -63,6 +174,69 @@ impl ScoreGraph {
         })
     }
 
+    /// Merges an override graph regrouping metrics, e.g. for a custom profile.
+    /// Every metric with a `dqv:inDimension` link in the override has its existing links
+    /// replaced. Fails unless each metric ends up in exactly one known dimension.
+    pub fn override_dimensions(&self, overrides: &str) -> Result<(), Error> {
+        let overrides = parse_graphs(vec![overrides])?;
+        let links = overrides
+            .quads_for_pattern(None, Some(dqv::IN_DIMENSION), None, None)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for link in links.iter() {
+            for existing in self
+                .0
+                .quads_for_pattern(
+                    Some(link.subject.as_ref()),
+                    Some(dqv::IN_DIMENSION),
+                    None,
+                    None,
+                )
+                .collect::<Result<Vec<_>, _>>()?
+            {
+                self.0.remove(&existing)?;
+            }
+        }
+        for link in links.iter() {
+            self.0.insert(link)?;
+        }
+
+        let dimensions = self.dimensions()?;
+        let metrics = self
+            .0
+            .quads_for_pattern(None, Some(rdf::TYPE), Some(dqv::METRIC.into()), None)
+            .map(named_quad_subject)
+            .collect::<Result<Vec<NamedNode>, Error>>()?;
+        for metric in metrics {
+            let metric_dimensions = self
+                .0
+                .quads_for_pattern(
+                    Some(metric.as_ref().into()),
+                    Some(dqv::IN_DIMENSION),
+                    None,
+                    None,
+                )
+                .map(named_quad_object)
+                .collect::<Result<Vec<NamedNode>, Error>>()?;
+            match metric_dimensions.as_slice() {
+                [dimension] if dimensions.contains(dimension) => (),
+                [dimension] => {
+                    return Err(
+                        format!("metric '{metric}' is in unknown dimension '{dimension}'").into(),
+                    )
+                }
+                _ => {
+                    return Err(format!(
+                        "metric '{metric}' must be in exactly one dimension, found {}",
+                        metric_dimensions.len()
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Retrieves all named dimensions.
     fn dimensions(&self) -> Result<Vec<NamedNode>, Error> {
         let mut dimensions = self
@@ -83,17 +257,22 @@ impl ScoreGraph {
     fn metrics(&self, dimension: NamedNodeRef) -> Result<Vec<ScoreMetric>, Error> {
         let q = format!(
             "
-                SELECT ?metric ?score
+                SELECT ?metric ?score ?reduction ?freshness
                 WHERE {{
                     ?metric a {} .
-                    ?metric {} {dimension} .
+                    ?metric {} {} .
                     ?metric {} ?score .
+                    OPTIONAL {{ ?metric {} ?reduction . }}
+                    OPTIONAL {{ ?metric {} ?freshness . }}
                 }}
                 ORDER BY ?metric
             ",
             dqv::METRIC,
             dqv::IN_DIMENSION,
+            sparql_iri(dimension)?,
             dcat_mqa::TRUE_SCORE,
+            dcat_mqa::MEASUREMENT_REDUCTION,
+            dcat_mqa::FRESHNESS_WINDOW_DAYS,
         );
         execute_query(&self.0, &q)?
             .into_iter()
@@ -105,21 +284,107 @@ impl ScoreGraph {
                 let score = match qs.get("score") {
                     Some(Term::Literal(literal)) => literal.value().parse::<u64>().map_err(|_| {
                         format!(
-                            "unable to parse metric score from score graph: '{}'",
+                            "unable to parse score of metric '{}' from score graph: '{}'",
+                            name,
                             literal.value()
                         )
                     }),
                     _ => Err("unable to read metric score from score graph".into()),
                 }?;
-                Ok(ScoreMetric { id: name, score })
+                let reduction = match qs.get("reduction") {
+                    Some(Term::Literal(literal)) => MeasurementReduction::try_from(literal.value())
+                        .map_err(|e| format!("invalid reduction of metric '{name}': {e}"))?,
+                    Some(_) => Err("unable to read metric reduction from score graph")?,
+                    None => MeasurementReduction::default(),
+                };
+                let freshness_window = match qs.get("freshness") {
+                    Some(Term::Literal(literal)) => {
+                        let days = literal.value().parse::<u32>().map_err(|_| {
+                            format!(
+                                "unable to parse freshness of metric '{}' from score graph: '{}'",
+                                name,
+                                literal.value()
+                            )
+                        })?;
+                        TimeDelta::days(days.into())
+                    }
+                    Some(_) => Err("unable to read metric freshness from score graph")?,
+                    None => TimeDelta::days(DEFAULT_FRESHNESS_WINDOW_DAYS),
+                };
+                Ok(ScoreMetric {
+                    id: name,
+                    score,
+                    reduction,
+                    freshness_window,
+                })
             })
             .collect()
     }
 }
 
+/// Loads score definitions from the bundled graphs, with score values read from file if given,
+/// and metrics regrouped by the dimension overrides file if given.
+pub fn load_score_definitions(
+    score_values_file: Option<&str>,
+    dimension_overrides_file: Option<&str>,
+) -> Result<ScoreDefinitions, Error> {
+    let score_graph = match score_values_file {
+        Some(path) => ScoreGraph::from_turtle(VOCAB_GRAPH, &fs::read_to_string(path)?)?,
+        None => ScoreGraph::new()?,
+    };
+    if let Some(path) = dimension_overrides_file {
+        score_graph.override_dimensions(&fs::read_to_string(path)?)?;
+    }
+    score_graph.scores()
+}
+
+/// Score definitions of the bundled graphs, parsed once on first use.
+pub fn default_score_definitions() -> &'static ScoreDefinitions {
+    &DEFAULT_SCORE_DEFINITIONS
+}
+
+/// Reloads score definitions and swaps them in for subsequent messages.
+/// Returns the new total score.
+pub fn reload_score_definitions(
+    shared: &SharedScoreDefinitions,
+    score_values_file: &str,
+    dimension_overrides_file: Option<&str>,
+) -> Result<u64, Error> {
+    let score_definitions =
+        load_score_definitions(Some(score_values_file), dimension_overrides_file)?;
+    let total_score = score_definitions.total_score;
+    shared.store(Arc::new(score_definitions));
+    Ok(total_score)
+}
+
 impl ScoreMetric {
-    /// Score a measurement value.
-    pub fn score(&self, value: &MeasurementValue) -> Result<u64, Error> {
+    /// Score the measurement values of a node, reduced by the metric's reduction strategy.
+    pub fn score(&self, values: &[MeasurementValue]) -> Result<u64, Error> {
+        let passed = match self.reduction {
+            MeasurementReduction::AnyTrue => {
+                let mut any_passed = false;
+                for value in values {
+                    any_passed |= self.passes(value)?;
+                }
+                any_passed
+            }
+            MeasurementReduction::Max => match values.iter().max_by_key(|value| self.rank(value)) {
+                Some(value) => self.passes(value)?,
+                None => false,
+            },
+            MeasurementReduction::Count => {
+                let mut passed_count = 0;
+                for value in values {
+                    passed_count += self.passes(value)? as u64;
+                }
+                return Ok(self.score * passed_count / (values.len() as u64).max(1));
+            }
+        };
+        Ok(if passed { self.score } else { 0 })
+    }
+
+    /// Whether a single measurement value passes the metric.
+    fn passes(&self, value: &MeasurementValue) -> Result<bool, Error> {
         use crate::vocab::dcat_mqa::*;
         use MeasurementValue::*;
 
@@ -133,26 +398,61 @@ impl ScoreMetric {
             },
             _ => match value {
                 Bool(bool) => Ok(bool.clone()),
+                DateTime(timestamp) => Ok(self.is_fresh(*timestamp, Utc::now().timestamp_millis())),
+                Uri(uri) => Ok(is_absolute_iri(uri)),
                 _ => Err(format!(
-                    "measurement '{}' must be of type bool: '{:?}'",
+                    "measurement '{}' must be of type bool, dateTime or anyURI: '{:?}'",
                     self.id, value
                 )),
             },
         }?;
-        Ok(if ok { self.score } else { 0 })
+        Ok(ok)
+    }
+
+    /// Orders measurement values from worst to best. Status codes rank successful codes above
+    /// all others, since a larger code is not a better one.
+    fn rank(&self, value: &MeasurementValue) -> (bool, i64) {
+        use crate::vocab::dcat_mqa::*;
+        use MeasurementValue::*;
+
+        match (self.id.as_ref(), value) {
+            (ACCESS_URL_STATUS_CODE | DOWNLOAD_URL_STATUS_CODE, Int(code)) => {
+                ((200..300).contains(code), -code)
+            }
+            (_, Int(int)) => (false, *int),
+            (_, Bool(bool)) => (false, *bool as i64),
+            (_, DateTime(timestamp)) => (false, *timestamp),
+            _ => (false, i64::MIN),
+        }
     }
+
+    /// Whether a timestamp is within the freshness window before now, both as epoch millis.
+    fn is_fresh(&self, timestamp: i64, now: i64) -> bool {
+        now - timestamp <= self.freshness_window.num_milliseconds()
+    }
+}
+
+/// Whether a URL-valued measurement is a nonempty, absolute IRI.
+fn is_absolute_iri(value: &str) -> bool {
+    NamedNode::new(value.trim()).is_ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::MeasurementValue;
     use super::*;
-    use crate::test::{mqa_node, METRIC_GRAPH, SCORE_GRAPH};
     use crate::vocab::dcat_mqa::*;
+    use crate::{
+        assessment_graph::AssessmentGraph,
+        score::calculate_score,
+        test::{mqa_node, MEASUREMENT_GRAPH, METRIC_GRAPH, SCORE_GRAPH},
+    };
     use oxigraph::model::NamedNode;
+    use std::env;
+    use uuid::Uuid;
 
     fn score_graph() -> ScoreGraph {
-        ScoreGraph(parse_graphs(vec![METRIC_GRAPH, SCORE_GRAPH]).unwrap())
+        ScoreGraph::from_turtle(METRIC_GRAPH, SCORE_GRAPH).unwrap()
     }
 
     #[test]
@@ -163,6 +463,16 @@ mod tests {
         )
     }
 
+    #[test]
+    fn default_definitions() {
+        let expected = ScoreGraph::new().unwrap().scores().unwrap();
+        assert_eq!(default_score_definitions(), &expected);
+        assert!(std::ptr::eq(
+            default_score_definitions(),
+            default_score_definitions()
+        ));
+    }
+
     #[test]
     fn score() {
         assert_eq!(
@@ -172,23 +482,14 @@ mod tests {
                     ScoreDimension {
                         id: mqa_node("accessibility"),
                         metrics: vec![
-                            ScoreMetric {
-                                id: mqa_node("accessUrlStatusCode"),
-                                score: 50
-                            },
-                            ScoreMetric {
-                                id: mqa_node("downloadUrlAvailability"),
-                                score: 20
-                            },
+                            ScoreMetric::new(mqa_node("accessUrlStatusCode"), 50),
+                            ScoreMetric::new(mqa_node("downloadUrlAvailability"), 20),
                         ],
                         total_score: 70,
                     },
                     ScoreDimension {
                         id: mqa_node("interoperability"),
-                        metrics: vec![ScoreMetric {
-                            id: mqa_node("formatAvailability"),
-                            score: 20
-                        }],
+                        metrics: vec![ScoreMetric::new(mqa_node("formatAvailability"), 20)],
                         total_score: 20,
                     }
                 ],
@@ -197,6 +498,256 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dimension_overrides() {
+        let graph = score_graph();
+        graph
+            .override_dimensions(
+                r#"
+                @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+                @prefix dqv: <http://www.w3.org/ns/dqv#> .
+                dcatno-mqa:downloadUrlAvailability  dqv:inDimension  dcatno-mqa:interoperability .
+                "#,
+            )
+            .unwrap();
+        let score_definitions = graph.scores().unwrap();
+
+        let totals = score_definitions
+            .dimensions
+            .iter()
+            .map(|dimension| (dimension.id.clone(), dimension.total_score))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            totals,
+            vec![
+                (mqa_node("accessibility"), 50),
+                (mqa_node("interoperability"), 40),
+            ]
+        );
+        assert_eq!(score_definitions.total_score, 90);
+        assert_eq!(
+            score_definitions.dimension_of(&mqa_node("downloadUrlAvailability")),
+            Some(&mqa_node("interoperability"))
+        );
+    }
+
+    #[test]
+    fn invalid_dimension_overrides() {
+        let err = score_graph()
+            .override_dimensions(
+                r#"
+                @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+                @prefix dqv: <http://www.w3.org/ns/dqv#> .
+                dcatno-mqa:downloadUrlAvailability  dqv:inDimension  dcatno-mqa:accessibility ,
+                                                                     dcatno-mqa:interoperability .
+                "#,
+            )
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("must be in exactly one dimension, found 2"));
+
+        let err = score_graph()
+            .override_dimensions(
+                r#"
+                @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+                @prefix dqv: <http://www.w3.org/ns/dqv#> .
+                dcatno-mqa:downloadUrlAvailability  dqv:inDimension  dcatno-mqa:profile .
+                "#,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("is in unknown dimension"));
+    }
+
+    #[test]
+    fn reload_changes_scoring() {
+        let shared: SharedScoreDefinitions = Arc::new(ArcSwap::from_pointee(
+            load_score_definitions(None, None).unwrap(),
+        ));
+        let measurement_graph = AssessmentGraph::new().unwrap();
+        measurement_graph.load(MEASUREMENT_GRAPH).unwrap();
+        let dataset_score = |shared: &SharedScoreDefinitions| {
+            let score_definitions = shared.load_full();
+            calculate_score(&measurement_graph, &score_definitions)
+                .unwrap()
+                .0
+                .score
+        };
+        assert_eq!(dataset_score(&shared), 70);
+
+        let path = env::temp_dir().join(format!("score-values-{}.ttl", Uuid::new_v4()));
+        fs::write(
+            &path,
+            SCORE_GRAPH
+                .replace("\"50\"", "\"10\"")
+                .replace("\"20\"", "\"5\""),
+        )
+        .unwrap();
+        let total_score = reload_score_definitions(&shared, path.to_str().unwrap(), None).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(total_score, 20);
+        assert_eq!(dataset_score(&shared), 15);
+    }
+
+    #[test]
+    fn malformed_metric_score() {
+        let graph = ScoreGraph::from_turtle(
+            METRIC_GRAPH,
+            r#"
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            dcatno-mqa:accessUrlStatusCode  dcatno-mqa:trueScore  "abc" .
+            "#,
+        )
+        .unwrap();
+
+        let err = graph.scores().unwrap_err().to_string();
+        assert!(err.contains("dcatno-mqa#accessUrlStatusCode"));
+        assert!(err.contains("'abc'"));
+    }
+
+    #[test]
+    fn custom_definitions() {
+        let scores = r#"
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            @prefix xsd:        <http://www.w3.org/2001/XMLSchema#> .
+            dcatno-mqa:accessUrlStatusCode      dcatno-mqa:trueScore  "10"^^xsd:integer .
+            dcatno-mqa:downloadUrlAvailability  dcatno-mqa:trueScore  "5"^^xsd:integer .
+            dcatno-mqa:formatAvailability       dcatno-mqa:trueScore  "1"^^xsd:integer .
+        "#;
+        let definitions = ScoreGraph::from_turtle(METRIC_GRAPH, scores)
+            .unwrap()
+            .scores()
+            .unwrap();
+        assert_eq!(definitions.total_score, 16);
+        assert_eq!(definitions.dimensions[0].total_score, 15);
+
+        let err = ScoreGraph::from_turtle("", scores)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("no dimensions"));
+        assert!(ScoreGraph::from_turtle(METRIC_GRAPH, "not turtle").is_err());
+
+        let vocab = format!(
+            r#"{METRIC_GRAPH}
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            @prefix dqv:        <http://www.w3.org/ns/dqv#> .
+            dcatno-mqa:timeliness  a  dqv:Dimension .
+        "#
+        );
+        let err = ScoreGraph::from_turtle(&vocab, scores)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("no scoring metric defined for dimension"));
+        assert!(err.contains("dcatno-mqa#timeliness"));
+    }
+
+    #[test]
+    fn metric_reduction() {
+        let scores = format!(
+            r#"{SCORE_GRAPH}
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            dcatno-mqa:accessUrlStatusCode  dcatno-mqa:measurementReduction  "max" .
+            "#
+        );
+        let definitions = ScoreGraph::from_turtle(METRIC_GRAPH, &scores)
+            .unwrap()
+            .scores()
+            .unwrap();
+        let reductions = definitions
+            .dimensions
+            .iter()
+            .flat_map(|dimension| dimension.metrics.iter().map(|metric| metric.reduction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reductions,
+            vec![
+                MeasurementReduction::Max,
+                MeasurementReduction::AnyTrue,
+                MeasurementReduction::AnyTrue,
+            ]
+        );
+
+        let err = ScoreGraph::from_turtle(METRIC_GRAPH, &scores.replace("\"max\"", "\"median\""))
+            .unwrap()
+            .scores()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dcatno-mqa#accessUrlStatusCode"));
+        assert!(err.contains("'median'"));
+    }
+
+    #[test]
+    fn metric_freshness_window() {
+        let scores = format!(
+            r#"{SCORE_GRAPH}
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            @prefix xsd:        <http://www.w3.org/2001/XMLSchema#> .
+            dcatno-mqa:formatAvailability  dcatno-mqa:freshnessWindowDays  "7"^^xsd:integer .
+            "#
+        );
+        let definitions = ScoreGraph::from_turtle(METRIC_GRAPH, &scores)
+            .unwrap()
+            .scores()
+            .unwrap();
+        assert_eq!(
+            definitions.dimensions[0].metrics[0].freshness_window,
+            TimeDelta::days(DEFAULT_FRESHNESS_WINDOW_DAYS)
+        );
+        assert_eq!(
+            definitions.dimensions[1].metrics[0].freshness_window,
+            TimeDelta::days(7)
+        );
+
+        let err = ScoreGraph::from_turtle(METRIC_GRAPH, &scores.replace("\"7\"", "\"-7\""))
+            .unwrap()
+            .scores()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dcatno-mqa#formatAvailability"));
+        assert!(err.contains("'-7'"));
+    }
+
+    #[test]
+    fn duplicate_metric_score() {
+        let scores = format!(
+            r#"{SCORE_GRAPH}
+            @prefix dcatno-mqa: <https://data.norge.no/vocabulary/dcatno-mqa#> .
+            @prefix xsd:        <http://www.w3.org/2001/XMLSchema#> .
+            dcatno-mqa:accessUrlStatusCode  dcatno-mqa:trueScore  "60"^^xsd:integer .
+            "#
+        );
+        let graph = ScoreGraph::from_turtle(METRIC_GRAPH, &scores).unwrap();
+
+        let err = graph.scores().unwrap_err().to_string();
+        assert!(err.contains("dcatno-mqa#accessUrlStatusCode"));
+        assert!(err.contains("duplicate metric"));
+    }
+
+    #[test]
+    fn metric_ids() {
+        let score_definitions = score_graph().scores().unwrap();
+        assert_eq!(
+            score_definitions.metric_ids(),
+            HashSet::from([
+                &mqa_node("accessUrlStatusCode"),
+                &mqa_node("downloadUrlAvailability"),
+                &mqa_node("formatAvailability"),
+            ])
+        );
+        assert_eq!(
+            score_definitions.dimension_of(&mqa_node("downloadUrlAvailability")),
+            Some(&mqa_node("accessibility"))
+        );
+        assert_eq!(
+            score_definitions.dimension_of(&mqa_node("formatAvailability")),
+            Some(&mqa_node("interoperability"))
+        );
+        assert_eq!(score_definitions.dimension_of(&mqa_node("unknown")), None);
+    }
+
     #[test]
     fn full_size_graph() {
         assert!(ScoreGraph::new().is_ok());
@@ -205,11 +756,11 @@ mod tests {
     #[test]
     fn url_int_measurement() {
         assert_eq!(
-            ScoreMetric {
-                id: NamedNode::new_unchecked(ACCESS_URL_STATUS_CODE.as_str()),
-                score: 20,
-            }
-            .score(&MeasurementValue::Int(200))
+            ScoreMetric::new(
+                NamedNode::new_unchecked(ACCESS_URL_STATUS_CODE.as_str()),
+                20
+            )
+            .score(&[MeasurementValue::Int(200)])
             .unwrap(),
             20
         );
@@ -217,40 +768,106 @@ mod tests {
 
     #[test]
     fn url_bool_measurement() {
-        assert!(ScoreMetric {
-            id: NamedNode::new_unchecked(DOWNLOAD_URL_STATUS_CODE.as_str()),
-            score: 20
-        }
-        .score(&MeasurementValue::Bool(true))
+        assert!(ScoreMetric::new(
+            NamedNode::new_unchecked(DOWNLOAD_URL_STATUS_CODE.as_str()),
+            20
+        )
+        .score(&[MeasurementValue::Bool(true)])
         .is_err());
     }
 
     #[test]
     fn bool_measurements() {
-        assert!(ScoreMetric {
-            id: NamedNode::new_unchecked(""),
-            score: 10
-        }
-        .score(&MeasurementValue::Int(10))
-        .is_err(),);
+        assert!(ScoreMetric::new(NamedNode::new_unchecked(""), 10)
+            .score(&[MeasurementValue::Int(10)])
+            .is_err(),);
 
         assert_eq!(
-            ScoreMetric {
-                id: NamedNode::new_unchecked(""),
-                score: 10
-            }
-            .score(&MeasurementValue::Bool(true))
-            .unwrap(),
+            ScoreMetric::new(NamedNode::new_unchecked(""), 10)
+                .score(&[MeasurementValue::Bool(true)])
+                .unwrap(),
             10
         );
         assert_eq!(
-            ScoreMetric {
-                id: NamedNode::new_unchecked(""),
-                score: 10
-            }
-            .score(&MeasurementValue::Bool(false))
-            .unwrap(),
+            ScoreMetric::new(NamedNode::new_unchecked(""), 10)
+                .score(&[MeasurementValue::Bool(false)])
+                .unwrap(),
             0
         );
     }
+
+    #[test]
+    fn uri_measurements() {
+        let metric = ScoreMetric::new(NamedNode::new_unchecked(""), 10);
+        let score = |uri: &str| {
+            metric
+                .score(&[MeasurementValue::Uri(uri.to_string())])
+                .unwrap()
+        };
+
+        assert_eq!(score("https://data.norge.no/nlod/no/2.0"), 10);
+        assert_eq!(score("data.norge.no/nlod"), 0);
+        assert_eq!(score("not a uri"), 0);
+        assert_eq!(score(""), 0);
+    }
+
+    #[test]
+    fn multi_valued_measurements() {
+        let metric = |reduction| ScoreMetric {
+            reduction,
+            ..ScoreMetric::new(
+                NamedNode::new_unchecked(ACCESS_URL_STATUS_CODE.as_str()),
+                20,
+            )
+        };
+        let values = [
+            MeasurementValue::Int(200),
+            MeasurementValue::Int(404),
+            MeasurementValue::Int(500),
+            MeasurementValue::Int(204),
+        ];
+
+        assert_eq!(
+            metric(MeasurementReduction::AnyTrue)
+                .score(&values)
+                .unwrap(),
+            20
+        );
+        assert_eq!(
+            metric(MeasurementReduction::Max).score(&values).unwrap(),
+            20
+        );
+        assert_eq!(
+            metric(MeasurementReduction::Max)
+                .score(&values[1..3])
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            metric(MeasurementReduction::Count).score(&values).unwrap(),
+            10
+        );
+        assert_eq!(metric(MeasurementReduction::Count).score(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn freshness_window() {
+        let metric = ScoreMetric {
+            freshness_window: TimeDelta::days(30),
+            ..ScoreMetric::new(NamedNode::new_unchecked(""), 10)
+        };
+        let now = 1656316912123;
+        let day = TimeDelta::days(1).num_milliseconds();
+
+        assert!(metric.is_fresh(now - 29 * day, now));
+        assert!(!metric.is_fresh(now - 31 * day, now));
+        assert_eq!(
+            metric
+                .score(&[MeasurementValue::DateTime(
+                    Utc::now().timestamp_millis() - day
+                )])
+                .unwrap(),
+            10
+        );
+    }
 }
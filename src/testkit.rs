@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use httptest::{matchers::any, responders::Responder, Expectation, Server, ServerBuilder};
+use tracing::{
+    field::{Field, Visit},
+    span::{Id, Record},
+};
+use tracing_subscriber::layer::{Context, Layer};
+use uuid::Uuid;
+
+use crate::{config::Config, json_conversion::UpdateRequest, kafka::assessment_url};
+
+/// Request received by `MockScoringApi`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub method: String,
+    pub path: String,
+    /// Header names are lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Call {
+    /// Value of header, if sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Canned assessment graph, optionally encoded, e.g. gzipped.
+struct Graph {
+    body: Bytes,
+    content_encoding: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    graphs: HashMap<String, Graph>,
+    /// Statuses to respond with, by method and path, before falling back to canned responses.
+    statuses: HashMap<(String, String), VecDeque<u16>>,
+    calls: Vec<Call>,
+}
+
+/// In-memory scoring api, serving canned assessment graphs and recording all calls.
+/// Assessments without a canned graph are not found, and all posted scores are accepted,
+/// unless other statuses are queued.
+pub struct MockScoringApi {
+    server: Server,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockScoringApi {
+    /// Runs mock scoring api on address, e.g. that of the configured `scoring_api_url`.
+    pub fn run(addr: SocketAddr) -> Self {
+        let server = ServerBuilder::new()
+            .bind_addr(addr)
+            .run()
+            .expect("unable to run mock scoring api");
+        let state = Arc::new(Mutex::new(State::default()));
+        server.expect(
+            Expectation::matching(any())
+                .times(..)
+                .respond_with(Recorder(state.clone())),
+        );
+        Self { server, state }
+    }
+
+    /// Runs mock scoring api on a free port.
+    pub fn start() -> Self {
+        Self::run(SocketAddr::from(([127, 0, 0, 1], 0)))
+    }
+
+    /// Base url of the mock scoring api.
+    pub fn url(&self) -> String {
+        self.server.url_str("").trim_end_matches('/').to_string()
+    }
+
+    /// Default config, independent of the environment, pointing at the mock scoring api.
+    pub fn config(&self) -> Config {
+        Config {
+            scoring_api_url: self.url(),
+            ..default_config()
+        }
+    }
+
+    /// Serves graph as existing assessment of `fdk_id`.
+    pub fn serve_graph(&self, fdk_id: &Uuid, graph: &str) {
+        self.serve_encoded_graph(fdk_id, None, graph.as_bytes().to_vec());
+    }
+
+    /// Serves already encoded graph as existing assessment of `fdk_id`, with the given
+    /// `Content-Encoding`, e.g. gzip.
+    pub fn serve_encoded_graph(
+        &self,
+        fdk_id: &Uuid,
+        content_encoding: Option<&str>,
+        body: Vec<u8>,
+    ) {
+        self.lock().graphs.insert(
+            assessment_path(fdk_id),
+            Graph {
+                body: Bytes::from(body),
+                content_encoding: content_encoding.map(str::to_string),
+            },
+        );
+    }
+
+    /// Responds to the next `method` requests for `fdk_id` with `statuses`, in order,
+    /// e.g. server errors before serving a canned graph.
+    pub fn queue_statuses(&self, method: &str, fdk_id: &Uuid, statuses: &[u16]) {
+        self.lock()
+            .statuses
+            .entry((method.to_string(), assessment_path(fdk_id)))
+            .or_default()
+            .extend(statuses);
+    }
+
+    /// All calls received so far.
+    pub fn calls(&self) -> Vec<Call> {
+        self.lock().calls.clone()
+    }
+
+    /// Calls received so far with `method` for `fdk_id`.
+    pub fn calls_to(&self, method: &str, fdk_id: &Uuid) -> Vec<Call> {
+        let path = assessment_path(fdk_id);
+        self.lock()
+            .calls
+            .iter()
+            .filter(|call| call.method == method && call.path == path)
+            .cloned()
+            .collect()
+    }
+
+    /// Asserts that scores were posted for `fdk_id`, returning the last posted update.
+    pub fn assert_posted(&self, fdk_id: &Uuid) -> UpdateRequest {
+        let call = self
+            .calls_to("POST", fdk_id)
+            .pop()
+            .unwrap_or_else(|| panic!("no scores posted for '{fdk_id}'"));
+        serde_json::from_slice(&call.body).expect("posted body is not an update request")
+    }
+
+    /// Asserts that no scores were posted for `fdk_id`.
+    pub fn assert_not_posted(&self, fdk_id: &Uuid) {
+        assert!(
+            self.calls_to("POST", fdk_id).is_empty(),
+            "scores posted for '{fdk_id}'"
+        );
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn default_config() -> Config {
+    Config::from_lookup(|_| None).expect("invalid default config")
+}
+
+fn assessment_path(fdk_id: &Uuid) -> String {
+    assessment_url("", &default_config().scoring_api_path, fdk_id)
+}
+
+struct Recorder(Arc<Mutex<State>>);
+
+impl Responder for Recorder {
+    fn respond<'a>(
+        &mut self,
+        req: &'a http::Request<Bytes>,
+    ) -> Pin<Box<dyn Future<Output = http::Response<Bytes>> + Send + 'a>> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let method = req.method().to_string();
+        let path = req.uri().path().to_string();
+        let queued = state
+            .statuses
+            .get_mut(&(method.clone(), path.clone()))
+            .and_then(VecDeque::pop_front);
+        let mut response = http::Response::builder();
+        let body = match (queued, req.method(), state.graphs.get(&path)) {
+            (Some(status), _, _) => {
+                response = response.status(status);
+                Bytes::new()
+            }
+            (None, &http::Method::GET, Some(graph)) => {
+                if let Some(encoding) = &graph.content_encoding {
+                    response = response.header(http::header::CONTENT_ENCODING, encoding);
+                }
+                graph.body.clone()
+            }
+            (None, &http::Method::GET, None) => {
+                response = response.status(404);
+                Bytes::new()
+            }
+            (None, &http::Method::POST, _) => {
+                response = response.status(202);
+                Bytes::new()
+            }
+            (None, _, _) => {
+                response = response.status(405);
+                Bytes::new()
+            }
+        };
+        state.calls.push(Call {
+            method,
+            path,
+            headers: req
+                .headers()
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.as_str().to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).to_string(),
+                    )
+                })
+                .collect(),
+            body: req.body().to_vec(),
+        });
+
+        let response = response.body(body).expect("invalid mock response");
+        Box::pin(async move { response })
+    }
+}
+
+/// Tracing layer capturing the fields of all events as strings.
+#[derive(Clone, Default)]
+pub struct CapturedEvents(pub Arc<Mutex<Vec<HashMap<String, String>>>>);
+
+struct Visitor(HashMap<String, String>);
+
+impl Visit for Visitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl<S: tracing::Subscriber> Layer<S> for CapturedEvents {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = Visitor(HashMap::new());
+        event.record(&mut visitor);
+        self.0.lock().unwrap().push(visitor.0);
+    }
+
+    /// Span fields recorded after creation are captured as events too.
+    fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = Visitor(HashMap::new());
+        values.record(&mut visitor);
+        self.0.lock().unwrap().push(visitor.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::MEASUREMENT_GRAPH;
+
+    #[tokio::test]
+    async fn records_calls() {
+        let api = MockScoringApi::start();
+        let (known, unknown) = (Uuid::new_v4(), Uuid::new_v4());
+        api.serve_graph(&known, MEASUREMENT_GRAPH);
+        let client = reqwest::Client::new();
+        let url = |fdk_id| assessment_url(&api.url(), &default_config().scoring_api_path, fdk_id);
+
+        let response = client.get(url(&known)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), MEASUREMENT_GRAPH);
+        assert_eq!(
+            client.get(url(&unknown)).send().await.unwrap().status(),
+            404
+        );
+
+        let update = serde_json::json!({
+            "turtle_assessment": "turtle",
+            "jsonld_assessment": "jsonld",
+            "scores": {
+                "dataset": {"id": "https://dataset.foo", "dimensions": [], "score": 0, "max_score": 0},
+                "distributions": [],
+            },
+        });
+        let response = client.post(url(&known)).json(&update).send().await.unwrap();
+        assert_eq!(response.status(), 202);
+
+        assert_eq!(api.calls().len(), 3);
+        assert_eq!(api.assert_posted(&known).turtle_assessment, "turtle");
+        api.assert_not_posted(&unknown);
+    }
+
+    #[tokio::test]
+    async fn queued_statuses() {
+        let api = MockScoringApi::start();
+        let fdk_id = Uuid::new_v4();
+        api.serve_graph(&fdk_id, MEASUREMENT_GRAPH);
+        api.queue_statuses("GET", &fdk_id, &[503, 502]);
+        let client = reqwest::Client::new();
+        let url = assessment_url(&api.url(), &default_config().scoring_api_path, &fdk_id);
+
+        let mut statuses = Vec::new();
+        for _ in 0..3 {
+            let response = client
+                .get(&url)
+                .header("x-request-id", "abc")
+                .send()
+                .await
+                .unwrap();
+            statuses.push(response.status().as_u16());
+        }
+        assert_eq!(statuses, vec![503, 502, 200]);
+
+        let calls = api.calls_to("GET", &fdk_id);
+        assert_eq!(calls.len(), 3);
+        assert!(calls
+            .iter()
+            .all(|call| call.header("X-Request-Id") == Some("abc")));
+        assert!(api.calls_to("POST", &fdk_id).is_empty());
+    }
+}
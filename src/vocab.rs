@@ -7,6 +7,22 @@ macro_rules! n {
 
 type N = oxigraph::model::NamedNodeRef<'static>;
 
+/// Prefixes declared when serializing human readable turtle.
+pub const PREFIXES: [(&str, &str); 5] = [
+    ("dcat", "http://www.w3.org/ns/dcat#"),
+    ("dct", "http://purl.org/dc/terms/"),
+    ("dcatno-mqa", "https://data.norge.no/vocabulary/dcatno-mqa#"),
+    ("dqv", "http://www.w3.org/ns/dqv#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// Namespaces with an https variant emitted by some producers, mapped to the http namespace
+/// matched by queries.
+pub const NAMESPACE_ALIASES: [(&str, &str); 2] = [
+    ("https://www.w3.org/ns/dcat#", "http://www.w3.org/ns/dcat#"),
+    ("https://www.w3.org/ns/dqv#", "http://www.w3.org/ns/dqv#"),
+];
+
 pub mod dcat {
     use super::N;
 
@@ -40,18 +56,67 @@ pub mod dcat_mqa {
     pub const CONTAINS_QUALITY_MEASUREMENT: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement");
     pub const TRUE_SCORE: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#trueScore");
+    pub const MEASUREMENT_REDUCTION: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#measurementReduction");
+    pub const FRESHNESS_WINDOW_DAYS: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#freshnessWindowDays");
     pub const SCORE: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#score");
     pub const SCORING: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#scoring");
     pub const ACCESS_URL_STATUS_CODE: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode");
     pub const DOWNLOAD_URL_STATUS_CODE: N =
         n!("https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlStatusCode");
+
+    pub const ACCESSIBILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#accessibility");
+    pub const CONTEXTUALITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#contextuality");
+    pub const FINDABILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#findability");
+    pub const INTEROPERABILITY: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#interoperability");
+    pub const REUSABILITY: N = n!("https://data.norge.no/vocabulary/dcatno-mqa#reusability");
+
+    pub const ACCESSIBILITY_SCORING: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#accessibilityScoring");
+    pub const CONTEXTUALITY_SCORING: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#contextualityScoring");
+    pub const FINDABILITY_SCORING: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#findabilityScoring");
+    pub const INTEROPERABILITY_SCORING: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#interoperabilityScoring");
+    pub const REUSABILITY_SCORING: N =
+        n!("https://data.norge.no/vocabulary/dcatno-mqa#reusabilityScoring");
+
+    /// Whether metric holds a total score inserted by scoring, of an assessment or a dimension.
+    pub fn is_scoring(metric: oxigraph::model::NamedNodeRef) -> bool {
+        matches!(
+            metric,
+            SCORING
+                | ACCESSIBILITY_SCORING
+                | CONTEXTUALITY_SCORING
+                | FINDABILITY_SCORING
+                | INTEROPERABILITY_SCORING
+                | REUSABILITY_SCORING
+        )
+    }
+
+    /// Metric holding the total score of a dimension.
+    pub fn dimension_scoring(dimension: oxigraph::model::NamedNodeRef) -> Option<N> {
+        match dimension {
+            ACCESSIBILITY => Some(ACCESSIBILITY_SCORING),
+            CONTEXTUALITY => Some(CONTEXTUALITY_SCORING),
+            FINDABILITY => Some(FINDABILITY_SCORING),
+            INTEROPERABILITY => Some(INTEROPERABILITY_SCORING),
+            REUSABILITY => Some(REUSABILITY_SCORING),
+            _ => None,
+        }
+    }
 }
 
 pub mod dcat_terms {
     use super::N;
 
     pub const MODIFIED: N = n!("http://purl.org/dc/terms/modified");
+    pub const CREATOR: N = n!("http://purl.org/dc/terms/creator");
+    pub const CREATED: N = n!("http://purl.org/dc/terms/created");
 }
 
 pub mod rdf_syntax {
@@ -59,3 +124,25 @@ pub mod rdf_syntax {
 
     pub const TYPE: N = n!("http://www.w3.org/1999/02/22-rdf-syntax-ns#type");
 }
+
+#[cfg(test)]
+mod tests {
+    use oxigraph::model::NamedNode;
+
+    use super::*;
+
+    #[test]
+    fn dimension_scoring() {
+        let scoring = dcat_mqa::dimension_scoring(dcat_mqa::ACCESSIBILITY).unwrap();
+        assert_eq!(
+            scoring.as_str(),
+            "https://data.norge.no/vocabulary/dcatno-mqa#accessibilityScoring"
+        );
+        assert!(NamedNode::new(scoring.as_str()).is_ok());
+
+        assert_eq!(
+            dcat_mqa::dimension_scoring(n!("https://data.norge.no/vocabulary/dcatno-mqa#unknown")),
+            None
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use fdk_mqa_scoring_service::{
+    assessment_graph::AssessmentGraph, score::calculate_score,
+    score_graph::default_score_definitions,
+};
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts allocations made by the current thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+// Installed in its own test binary, so that it doesn't slow down other tests.
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Assessment graph of a dataset with `distribution_count` distributions, each with 3 measurements.
+fn assessment_graph(distribution_count: usize) -> AssessmentGraph {
+    let dataset = r#"
+        <https://dataset.assessment> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+        <https://dataset.assessment> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset> .
+    "#;
+    let distributions = (0..distribution_count)
+        .map(|i| {
+            format!(
+                r#"
+                <https://distribution.assessment.{i}> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DistributionAssessment> .
+                <https://distribution.assessment.{i}> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://distribution.{i}> .
+                <https://distribution.assessment.{i}> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a{i} .
+                <https://distribution.assessment.{i}> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:b{i} .
+                <https://distribution.assessment.{i}> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:c{i} .
+                _:a{i} <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#accessUrlStatusCode> .
+                _:a{i} <http://www.w3.org/ns/dqv#value> "200"^^<http://www.w3.org/2001/XMLSchema#integer> .
+                _:b{i} <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability> .
+                _:b{i} <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                _:c{i} <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#formatAvailability> .
+                _:c{i} <http://www.w3.org/ns/dqv#value> "false"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+                "#
+            )
+        })
+        .collect::<String>();
+    let graph = AssessmentGraph::new().unwrap();
+    graph.load(format!("{dataset}{distributions}")).unwrap();
+    graph
+}
+
+/// Allocations made by scoring a dataset with `distribution_count` distributions.
+fn scoring_allocations(distribution_count: usize) -> usize {
+    let score_definitions = default_score_definitions();
+    let graph = assessment_graph(distribution_count);
+
+    let before = ALLOCATIONS.with(Cell::get);
+    let (_, distribution_scores) = calculate_score(&graph, score_definitions).unwrap();
+    let allocations = ALLOCATIONS.with(Cell::get) - before;
+
+    assert_eq!(distribution_scores.len(), distribution_count);
+    allocations
+}
+
+#[test]
+fn scoring_allocations_per_distribution() {
+    let per_distribution = (scoring_allocations(500) - scoring_allocations(250)) / 250;
+    // Mostly reading measurements from the store. Scoring a node allocates only its dimension
+    // and metric lists, as lookups of interned measurements don't allocate.
+    assert!(
+        per_distribution <= 200,
+        "{per_distribution} allocations per scored distribution"
+    );
+}
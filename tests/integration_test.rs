@@ -1,21 +1,20 @@
-use std::{
-    fmt,
-    net::{IpAddr, Ipv4Addr, SocketAddr},
-};
+use std::time::Duration;
 
 use fdk_mqa_scoring_service::{
+    config::{Config, Sink},
+    error::Error,
     json_conversion::Scores,
-    kafka::{create_consumer, INPUT_TOPIC},
-    schemas::{MqaEvent, MqaEventType},
+    kafka::{create_consumer, create_score_producer, CONFIG},
+    schemas::{MqaEvent, ScoreEvent},
+    testkit::MockScoringApi,
 };
-use httptest::{
-    matchers::{all_of, json_decoded, request, ExecutionContext, Matcher},
-    responders::status_code,
-    Expectation, Server, ServerBuilder,
+use kafka_utils::{
+    consume_all_messages, consume_single_message, create_topic_consumer,
+    process_single_message_with, sr_settings, TestProducer,
 };
-use kafka_utils::{consume_all_messages, process_single_message, TestProducer};
-use serde::{Deserialize, Serialize};
-use sophia_api::term::SimpleTerm;
+use rdkafka::{consumer::StreamConsumer, Message};
+use schema_registry_converter::async_impl::avro::AvroDecoder;
+use sophia_api::term::{SimpleTerm, Term};
 use sophia_api::source::TripleSource;
 use sophia_isomorphism::isomorphic_graphs;
 use sophia_turtle::parser::turtle::parse_str;
@@ -25,16 +24,15 @@ mod kafka_utils;
 
 #[tokio::test]
 async fn test() {
-    let mut server = ServerBuilder::new()
-        .bind_addr(SocketAddr::new(
-            IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-            8082,
-        ))
-        .run()
-        .unwrap();
+    let api = MockScoringApi::start();
+    let config = Config {
+        scoring_api_url: api.url(),
+        ..CONFIG.clone()
+    };
 
     assert_transformation(
-        &server,
+        &api,
+        &config,
         r#"
             <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
             <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
@@ -59,7 +57,8 @@ async fn test() {
     ).await;
 
     assert_transformation(
-        &server,
+        &api,
+        &config,
         r#"
             <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
             <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
@@ -83,94 +82,206 @@ async fn test() {
         include_str!("data/second_half_of_dataset/scores.json"),
     ).await;
 
-    // Assert that scoring api received expected requests.
-    server.verify_and_clear();
+    // Messages without payload are skipped, without any scoring api requests.
+    let call_count = api.calls().len();
+    let consumer = create_consumer(&config).unwrap();
+    consume_all_messages(&consumer).await.unwrap();
+    let processor = process_single_message_with(consumer, &config);
+    TestProducer::new(&CONFIG.input_topics[0])
+        .produce_tombstone()
+        .await;
+    processor.await.unwrap();
+    assert_eq!(api.calls().len(), call_count);
+
+    // Scores are produced to the output topic when sinking scores to kafka, without posting.
+    assert_score_event(&api, &config).await;
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct UpdateRequest {
-    pub turtle_assessment: String,
-    pub scores: Scores,
+async fn assert_score_event(api: &MockScoringApi, config: &Config) {
+    let config = Config {
+        sink: Sink::Kafka,
+        output_topic: Some("mqa-scores".to_string()),
+        ..config.clone()
+    };
+    let output_consumer = create_topic_consumer("mqa-scores");
+    consume_all_messages(&output_consumer).await.unwrap();
+    let consumer = create_consumer(&config).unwrap();
+    consume_all_messages(&consumer).await.unwrap();
+    let processor = process_single_message_with(consumer, &config);
+
+    let uuid = Uuid::new_v4();
+    let input_message = MqaEvent::builder()
+        .fdk_id(uuid)
+        .graph(
+            r#"
+            <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+            _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability> .
+            "#,
+        )
+        .build()
+        .unwrap();
+    TestProducer::new(&CONFIG.input_topics[0])
+        .produce(&input_message, "no.fdk.mqa.MQAEvent")
+        .await;
+    processor.await.unwrap();
+
+    let event = consume_score_event(&output_consumer)
+        .await
+        .expect("no score event produced within timeout duration");
+    assert_eq!(event.fdk_id, uuid.to_string());
+    assert_eq!(event.timestamp, input_message.timestamp);
+    serde_json::from_str::<Scores>(&event.scores).unwrap();
+    api.assert_not_posted(&uuid);
 }
 
-impl Matcher<UpdateRequest> for UpdateRequest {
-    fn matches(&mut self, update: &UpdateRequest, _ctx: &mut ExecutionContext) -> bool {
-        println!("{}", update.turtle_assessment);
-        println!("{}", serde_json::to_string(&update.scores).unwrap());
-
-        assert!(isomorphic_graphs(
-            &parse_str(&update.turtle_assessment).collect_triples::<Vec<[SimpleTerm; 3]>>().unwrap(),
-            &parse_str(&self.turtle_assessment).collect_triples::<Vec<[SimpleTerm; 3]>>().unwrap()
-        ).unwrap());
-        assert_eq!(update.scores, self.scores);
-        true
-    }
+#[tokio::test]
+async fn test_score_producer() {
+    let api = MockScoringApi::start();
+    let config = Config {
+        scoring_api_url: api.url(),
+        sink: Sink::Both,
+        output_topic: Some("mqa-scores".to_string()),
+        ..CONFIG.clone()
+    };
+    let output_consumer = create_topic_consumer("mqa-scores");
+    consume_all_messages(&output_consumer).await.unwrap();
 
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(self, f)
-    }
+    // Produced events are consumed from the output topic as is.
+    let producer = create_score_producer(&config, sr_settings())
+        .unwrap()
+        .expect("no score producer when sinking scores to both");
+    let event = ScoreEvent {
+        fdk_id: Uuid::new_v4().to_string(),
+        scores: r#"{"dataset":{"id":"https://dataset.foo","dimensions":[],"score":0,"max_score":0},"distributions":[]}"#.to_string(),
+        timestamp: 1647698566000,
+    };
+    producer.produce(&event).await.unwrap();
+    assert_eq!(consume_score_event(&output_consumer).await, Some(event));
+
+    // Scores are produced once posted, matching the posted scores.
+    let uuid = Uuid::new_v4();
+    process_score_input(&config, uuid).await.unwrap();
+    let update = api.assert_posted(&uuid);
+    let event = consume_score_event(&output_consumer)
+        .await
+        .expect("no score event produced within timeout duration");
+    assert_eq!(event.fdk_id, uuid.to_string());
+    assert_eq!(
+        serde_json::from_str::<Scores>(&event.scores).unwrap(),
+        update.scores
+    );
+
+    // Scores are not produced when posting them fails.
+    let uuid = Uuid::new_v4();
+    api.queue_statuses("POST", &uuid, &[500]);
+    assert!(process_score_input(&config, uuid).await.is_err());
+    assert_eq!(api.calls_to("POST", &uuid).len(), 1);
+    assert_eq!(consume_score_event(&output_consumer).await, None);
+}
+
+/// Processes an event with a single measurement of a dataset with `fdk_id`.
+async fn process_score_input(config: &Config, fdk_id: Uuid) -> Result<(), Error> {
+    let consumer = create_consumer(config).unwrap();
+    consume_all_messages(&consumer).await.unwrap();
+    let processor = process_single_message_with(consumer, config);
+
+    let input_message = MqaEvent::builder()
+        .fdk_id(fdk_id)
+        .graph(
+            r#"
+            <https://dataset.assessment.foo> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <https://data.norge.no/vocabulary/dcatno-mqa#DatasetAssessment> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#assessmentOf> <https://dataset.foo> .
+            <https://dataset.assessment.foo> <https://data.norge.no/vocabulary/dcatno-mqa#containsQualityMeasurement> _:a .
+            _:a <http://www.w3.org/ns/dqv#value> "true"^^<http://www.w3.org/2001/XMLSchema#boolean> .
+            _:a <http://www.w3.org/ns/dqv#isMeasurementOf> <https://data.norge.no/vocabulary/dcatno-mqa#downloadUrlAvailability> .
+            "#,
+        )
+        .build()
+        .unwrap();
+    TestProducer::new(&CONFIG.input_topics[0])
+        .produce(&input_message, "no.fdk.mqa.MQAEvent")
+        .await;
+    processor.await
+}
+
+/// Consumes and decodes a single score event, if produced within the timeout period.
+async fn consume_score_event(consumer: &StreamConsumer) -> Option<ScoreEvent> {
+    let message = consume_single_message(consumer, Duration::from_millis(3000))
+        .await
+        .unwrap()?;
+    let decoded = AvroDecoder::new(sr_settings())
+        .decode(message.payload())
+        .await
+        .unwrap();
+    Some(apache_avro::from_value(&decoded.value).unwrap())
+}
+
+/// Asserts that graphs are isomorphic, ignoring provenance triples that carry generation time
+/// and are not part of expected graphs.
+fn assert_isomorphic(turtle: &str, expected: &str) {
+    let without_provenance = |turtle| {
+        parse_str(turtle)
+            .collect_triples::<Vec<[SimpleTerm; 3]>>()
+            .unwrap()
+            .into_iter()
+            .filter(|[_, p, _]| {
+                !matches!(
+                    p.iri().as_ref().map(|iri| iri.as_str()),
+                    Some("http://purl.org/dc/terms/creator" | "http://purl.org/dc/terms/created")
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(
+        isomorphic_graphs(&without_provenance(turtle), &without_provenance(expected)).unwrap(),
+        "{turtle}"
+    );
 }
 
 async fn assert_transformation(
-    server: &Server,
+    api: &MockScoringApi,
+    config: &Config,
     input: &str,
     api_response: Option<&'static str>,
     expected_ttl: &str,
     expected_json: &str,
 ) {
-    let consumer = create_consumer().unwrap();
+    let consumer = create_consumer(config).unwrap();
     // Clear topic of all existing messages.
     consume_all_messages(&consumer).await.unwrap();
     // Start async node-namer process.
-    let processor = process_single_message(consumer);
+    let processor = process_single_message_with(consumer, config);
 
     // Create MQA test event.
     let uuid = Uuid::new_v4();
-    let input_message = MqaEvent {
-        event_type: MqaEventType::PropertiesChecked,
-        timestamp: 1647698566000,
-        fdk_id: uuid.to_string(),
-        graph: input.to_string(),
-    };
+    let input_message = MqaEvent::builder()
+        .timestamp(1647698566000)
+        .fdk_id(uuid)
+        .graph(input)
+        .build()
+        .unwrap();
 
-    // Configure scoring api responses.
-    match api_response {
-        // Simulate existing event beeing processed.
-        Some(graph) => server.expect(
-            Expectation::matching(all_of![
-                request::method("GET"),
-                request::path(format!("/api/assessments/{}", uuid)),
-            ])
-            .respond_with(status_code(200).body(graph)),
-        ),
-        // Dataset never processed before.
-        None => server.expect(
-            Expectation::matching(all_of![
-                request::method("GET"),
-                request::path(format!("/api/assessments/{}", uuid)),
-            ])
-            .respond_with(status_code(404)),
-        ),
+    // Simulate existing event beeing processed, datasets never processed before are not found.
+    if let Some(graph) = api_response {
+        api.serve_graph(&uuid, graph);
     }
-    server.expect(
-        Expectation::matching(all_of![
-            request::method("POST"),
-            request::path(format!("/api/assessments/{}", uuid)),
-            request::body(json_decoded::<UpdateRequest, UpdateRequest>(
-                UpdateRequest {
-                    turtle_assessment: expected_ttl.to_string(),
-                    scores: serde_json::from_str(expected_json).unwrap(),
-                }
-            )),
-        ])
-        .respond_with(status_code(202)),
-    );
 
     // Produce message to topic.
-    TestProducer::new(&INPUT_TOPIC)
+    TestProducer::new(&CONFIG.input_topics[0])
         .produce(&input_message, "no.fdk.mqa.MQAEvent")
         .await;
 
     // Wait for node-namer to process message and assert result is ok.
     processor.await.unwrap();
+
+    // Assert that scoring api received expected scores.
+    let update = api.assert_posted(&uuid);
+    assert_isomorphic(&update.turtle_assessment, expected_ttl);
+    assert_eq!(
+        update.scores,
+        serde_json::from_str::<Scores>(expected_json).unwrap()
+    );
 }
@@ -2,9 +2,10 @@ use std::time::Duration;
 
 use fdk_mqa_scoring_service::{
     assessment_graph::AssessmentGraph,
+    config::Config,
     error::Error,
-    kafka::{handle_message, BROKERS},
-    score_graph::ScoreGraph,
+    kafka::{create_http_client, create_score_producer, handle_message, CONFIG},
+    score_graph::default_score_definitions,
 };
 use rdkafka::{
     consumer::{CommitMode, Consumer, StreamConsumer},
@@ -50,11 +51,16 @@ pub async fn consume_single_message(
     }
 }
 
-pub async fn process_single_message(consumer: StreamConsumer) -> Result<(), Error> {
+/// Processes a single message with the given configuration, e.g. one sinking scores to kafka.
+pub async fn process_single_message_with(
+    consumer: StreamConsumer,
+    config: &Config,
+) -> Result<(), Error> {
     let mut decoder = AvroDecoder::new(sr_settings());
-    let score_definitions = ScoreGraph::new()?.scores()?;
+    let score_definitions = default_score_definitions();
     let assessment_graph = AssessmentGraph::new()?;
-    let http_client = reqwest::Client::new();
+    let http_client = create_http_client(config)?;
+    let score_producer = create_score_producer(config, sr_settings())?;
 
     let timeout_duration = Duration::from_millis(3000);
     let message = consume_single_message(&consumer, timeout_duration)
@@ -62,15 +68,31 @@ pub async fn process_single_message(consumer: StreamConsumer) -> Result<(), Erro
         .expect("no message received within timeout duration");
 
     handle_message(
+        config,
         &mut decoder,
-        &score_definitions,
+        score_definitions,
         &assessment_graph,
         &http_client,
+        score_producer.as_ref(),
         &message,
     )
     .await
 }
 
+/// Creates consumer of a topic other than the input topic, e.g. the output topic.
+pub fn create_topic_consumer(topic: &str) -> StreamConsumer {
+    let consumer: StreamConsumer = ClientConfig::new()
+        .set("group.id", "fdk-mqa-scoring-service-test")
+        .set("bootstrap.servers", CONFIG.brokers.clone())
+        .set("auto.offset.reset", "earliest")
+        .create()
+        .expect("Failed to create Kafka StreamConsumer");
+    consumer
+        .subscribe(&[topic])
+        .expect("Failed to subscribe to topic");
+    consumer
+}
+
 pub fn sr_settings() -> SrSettings {
     let schema_registry = "http://localhost:8081";
     SrSettings::new_builder(schema_registry.to_string())
@@ -88,7 +110,7 @@ pub struct TestProducer<'a> {
 impl TestProducer<'_> {
     pub fn new(topic: &'static str) -> Self {
         let producer = ClientConfig::new()
-            .set("bootstrap.servers", BROKERS.clone())
+            .set("bootstrap.servers", CONFIG.brokers.clone())
             .create::<FutureProducer>()
             .expect("Failed to create Kafka FutureProducer");
 
@@ -115,4 +137,13 @@ impl TestProducer<'_> {
             .await
             .unwrap();
     }
+
+    /// Produces a message without payload.
+    pub async fn produce_tombstone(&mut self) {
+        let record: FutureRecord<String, Vec<u8>> = FutureRecord::to(self.topic);
+        self.producer
+            .send(record, Duration::from_secs(0))
+            .await
+            .unwrap();
+    }
 }